@@ -59,29 +59,30 @@ pub fn LatexDemo(id: Signal<ThingId>) -> impl IntoView {
 			cas::contexts::scalar::real::IR1Expr::from_latex_tokens(latex)
 		};
 		let ir2 = move || -> Result<_, cas::contexts::scalar::real::Error> {
-			let res = ir_1()?;
-			res
-				.into_iter()
-				.map(|tokens| cas::contexts::scalar::real::IR2Exprs::from_ir1(tokens))
-				.collect::<Result<Vec<_>, _>>()
+			ir_1()?.try_map(cas::contexts::scalar::real::IR2Exprs::from_ir1)
 		};
 		let ir3 = move || -> Result<_, cas::contexts::scalar::real::Error> {
-			let res = ir2()?;
-			Ok(
-				res
-					.into_iter()
-					.map(|tokens| cas::contexts::scalar::real::IR3Expr::from_ir2(tokens))
-					.collect::<Vec<_>>(),
-			)
+			Ok(ir2()?.map(cas::contexts::scalar::real::IR3Expr::from_ir2))
+		};
+
+		let error_span = move || match ir3() {
+			Ok(_) => None,
+			Err(err) => err.span(),
 		};
 
 		Ok(view! {
 			<MathQuillField latex />
 			<p> { move || format!("Raw latex: {}", latex.get()) } </p>
+			<p> { move || highlight_span(&latex.read(), error_span()) } </p>
 			<p> { move || match latex_ast() {
 				Ok(ast) => format!("Successfully parsed: {:?}", ast),
 				Err(err) => format!("Couldn't parse what you have typed: {}", err),
 			} } </p>
+			<p> { move || {
+				let (ast, errors) = latex_parser::parse_latex_lenient(&latex.read());
+				let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+				format!("Parsed as much as possible: {:?}, errors: {:?}", ast, errors)
+			} } </p>
 			<p> { move || match ir_1() {
 				Ok(ir) => format!("Successfully converted to IR1: {:?}", ir),
 				Err(err) => format!("Couldn't convert to IR1: {}", err),
@@ -104,3 +105,18 @@ pub fn LatexDemo(id: Signal<ThingId>) -> impl IntoView {
 		}
 	}
 }
+
+/// Underlines the part of `latex` that `span` points to
+fn highlight_span(latex: &str, span: Option<latex_parser::Span>) -> Option<impl IntoView + use<>> {
+	let span = span?;
+	let before = latex.get(..span.start)?.to_owned();
+	let mut at = latex.get(span.range())?.to_owned();
+	if at.is_empty() {
+		// e.g. the end of the latex
+		at.push(' ');
+	}
+	let after = latex.get(span.end..)?.to_owned();
+	Some(view! {
+		"Problem here: " <code> {before} <u> {at} </u> {after} </code>
+	})
+}
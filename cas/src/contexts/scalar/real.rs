@@ -6,10 +6,10 @@ use std::{
 	hash::Hash,
 };
 
-use crate::prelude::*;
-use bevy_ecs::{bundle::Bundle, component::Component, entity::Entity, world::World};
+use crate::{prelude::*, storage::Storage};
+use bevy_ecs::{bundle::Bundle, component::Component, system::Resource, world::World};
 use expr::{ConstantNum, Equation};
-use latex_parser::{Bracketed, Frac, Ident, LatexToken, LatexTokens};
+use latex_parser::{Bracketed, Frac, Ident, LatexToken, LatexTokens, Span};
 use num::bigint::BigUint;
 
 pub use from_latex::*;
@@ -45,43 +45,107 @@ pub mod things {
 }
 
 pub struct RealScalarStorage {
-	context: ContextOneVarEq<Ident>,
-	world: World,
-	start: Entity,
+	/// With the [`ContextOneVarEq`] as a resource for passes
+	storage: Storage,
 }
 
 impl RealScalarStorage {
 	pub fn from_latext_eq(latex: String) -> Result<Self, Error> {
 		let latex = latex_parser::LatexTokens::parse_from_latex(&latex)?;
 
-		let context = ContextOneVarEq::infer_variable(&latex);
-		// let eq = Equation::from_tokens(&context, &latex);
+		let context = ContextOneVarEq::infer_variable(&latex)?;
+		let eq = Equation::from_latex(&context, latex)?;
 
-		let world = World::new();
+		let mut world = World::new();
+		world.insert_resource(context);
+		let storage = Storage::with_world(world, Line::new(eq));
 
-		// let first_line = world.spawn(Line {
-		//   eq:
-		//   is_eq: IsEquation,
-		// });
+		Ok(RealScalarStorage { storage })
+	}
+
+	pub fn context(&self) -> &ContextOneVarEq<Ident> {
+		self.storage.world().resource()
+	}
 
-		// RealScalarContext {
-		//   solve_for,
-		//   world,
-		// }
+	pub fn storage(&self) -> &Storage {
+		&self.storage
+	}
 
-		todo!()
+	/// Runs [`pass::eqs::solve_steps`] on the latest line,
+	/// leaving a line for every step
+	pub fn solve(&mut self) -> Result<pass::eqs::Solution, Error> {
+		let solving = self.storage.latest().id();
+		self.storage.run_pass(pass::eqs::solve_steps)?;
+		let solution = self
+			.storage
+			.world()
+			.get::<pass::eqs::Solution>(solving)
+			.expect("solve_steps to add the solution");
+		Ok(solution.clone())
+	}
+}
+
+/// Several equations in several variables,
+/// e.g. one per input field
+pub struct RealScalarSystem {
+	context: ContextLinearSystem<Ident>,
+	equations: Vec<Equation<Ident>>,
+}
+
+impl RealScalarSystem {
+	pub fn from_latex_eqs(latex: impl IntoIterator<Item = String>) -> Result<Self, Error> {
+		let tokens = latex
+			.into_iter()
+			.enumerate()
+			.map(|(index, latex)| {
+				LatexTokens::parse_from_latex(&latex).map_err(|err| Error::in_equation(index, err.into()))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let context = ContextLinearSystem::infer_variables(&tokens)?;
+		let equations = tokens
+			.into_iter()
+			.enumerate()
+			.map(|(index, tokens)| {
+				Equation::from_latex(&context, tokens).map_err(|err| Error::in_equation(index, err))
+			})
+			.collect::<Result<_, _>>()?;
+
+		Ok(RealScalarSystem { context, equations })
+	}
+
+	pub fn context(&self) -> &ContextLinearSystem<Ident> {
+		&self.context
+	}
+
+	pub fn equations(&self) -> &[Equation<Ident>] {
+		&self.equations
+	}
+
+	/// See [`pass::system::solve_system`]
+	pub fn solve(&self) -> Result<pass::system::SystemSolution, Error> {
+		pass::system::solve_system(&self.context, &self.equations)
 	}
 }
 
 #[derive(Component)]
-struct IsEquation;
+pub struct IsEquation;
 
 #[derive(Bundle)]
-struct Line {
+pub struct Line {
 	eq: Equation<Ident>,
 	is_eq: IsEquation,
 }
 
+impl Line {
+	pub fn new(eq: Equation<Ident>) -> Self {
+		Line {
+			eq,
+			is_eq: IsEquation,
+		}
+	}
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
 	#[error(
@@ -101,16 +165,107 @@ pub enum Error {
 	#[error("Typing required ...")]
 	NoTokens,
 
+	#[error("Equation {}: {err}", index + 1)]
+	InEquation { index: usize, err: Box<Error> },
+
+	#[error("This needs an = sign to be an equation")]
+	NoEquals,
+
+	#[error("Only one = sign is supported at the moment")]
+	MultipleRelations { span: Span },
+
+	#[error("Inequalities aren't supported yet")]
+	UnsupportedRelation { span: Span },
+
+	#[error("Only linear equations like 2x + 1 = 5 can be solved at the moment")]
+	NotLinear,
+
+	#[error("Only polynomial equations like x^2 + 2x = 3 can be solved at the moment")]
+	NotPolynomial,
+
+	#[error("Equations with π or τ can't be solved exactly yet")]
+	IrrationalConstant,
+
+	#[error("You can't divide by zero")]
+	DivideByZero,
+
+	#[error("This power is too big to work with exactly")]
+	PowerTooLarge,
+
 	// #[error("You gotta write stuff around the = sign bro")]
 	// EmptyAroundEq,
-	#[error("Why are you putting equals signs there? Don't nest them please!")]
-	CantNestEq,
+	#[error("Why are you putting equals or inequality signs there? Don't nest them please!")]
+	CantNestEq { span: Span },
 
 	#[error("You can't just list operators like times or minus, give them some numbers")]
-	CantListOperators,
+	CantListOperators { span: Span },
 
 	#[error("You can't finish your expression with + or -, add something after it")]
-	CantEndOnAddNeg,
+	CantEndOnAddNeg { span: Span },
+
+	#[error("This number is too big or too small to work with exactly")]
+	NumberTooLarge { span: Span },
+
+	#[error("Functions like {name} aren't supported yet")]
+	UnsupportedFunction {
+		name: latex_parser::FunctionName,
+		span: Span,
+	},
+
+	#[error("Absolute values aren't supported yet")]
+	UnsupportedAbs { span: Span },
+
+	#[error("Sums, products, integrals and limits aren't supported yet")]
+	UnsupportedCalculus { span: Span },
+
+	#[error("Matrices and vectors aren't real numbers")]
+	UnsupportedMatrix { span: Span },
+
+	#[error("Part of this couldn't be understood")]
+	InvalidLatex { span: Span },
+
+	#[error(transparent)]
+	MissingResource(#[from] crate::storage::MissingResource),
+}
+
+impl Error {
+	/// Which of several equations `err` is from, starting at 0
+	pub fn in_equation(index: usize, err: Error) -> Self {
+		Error::InEquation {
+			index,
+			err: Box::new(err),
+		}
+	}
+
+	/// The part of the latex the error is about, if known
+	pub fn span(&self) -> Option<Span> {
+		match self {
+			Error::ParseLatex(err) => Some(err.span()),
+			Error::InEquation { err, .. } => err.span(),
+			Error::CantNestEq { span }
+			| Error::MultipleRelations { span }
+			| Error::UnsupportedRelation { span }
+			| Error::CantListOperators { span }
+			| Error::CantEndOnAddNeg { span }
+			| Error::NumberTooLarge { span }
+			| Error::UnsupportedFunction { span, .. }
+			| Error::UnsupportedAbs { span }
+			| Error::UnsupportedCalculus { span }
+			| Error::UnsupportedMatrix { span }
+			| Error::InvalidLatex { span } => Some(*span),
+			Error::UndefinedIdent(_)
+			| Error::MultipleVariables(_)
+			| Error::NoVariables
+			| Error::NoTokens
+			| Error::NoEquals
+			| Error::NotLinear
+			| Error::NotPolynomial
+			| Error::IrrationalConstant
+			| Error::DivideByZero
+			| Error::PowerTooLarge
+			| Error::MissingResource(_) => None,
+		}
+	}
 }
 
 pub enum OneVariableEquation {
@@ -121,6 +276,10 @@ pub enum OneVariableEquation {
 
 impl latex_parser::TokenVisitor for OneVariableEquation {
 	fn visit_ident(&mut self, ident: &latex_parser::Ident) {
+		// constants, not variables
+		if matches!(ident, Ident::Pi | Ident::Tau) {
+			return;
+		}
 		match self {
 			OneVariableEquation::NoVariables => {
 				*self = OneVariableEquation::Ok {
@@ -144,6 +303,7 @@ impl latex_parser::TokenVisitor for OneVariableEquation {
 	}
 }
 
+#[derive(Resource)]
 pub struct ContextOneVarEq<Var> {
 	solve_for: Var,
 	constants: HashMap<Var, ConstantNum>,
@@ -165,17 +325,116 @@ impl ContextOneVarEq<Ident> {
 	}
 }
 
+/// Every variable in the order they first appear
+#[derive(Default)]
+pub struct Variables(Vec<Ident>);
+
+impl latex_parser::TokenVisitor for Variables {
+	fn visit_ident(&mut self, ident: &latex_parser::Ident) {
+		// constants, not variables
+		if matches!(ident, Ident::Pi | Ident::Tau) || self.0.contains(ident) {
+			return;
+		}
+		self.0.push(ident.clone());
+	}
+}
+
+/// Solving several equations at once for every variable in them
+#[derive(Resource)]
+pub struct ContextLinearSystem<Var> {
+	unknowns: Vec<Var>,
+	constants: HashMap<Var, ConstantNum>,
+}
+
+impl ContextLinearSystem<Ident> {
+	pub fn infer_variables(equations: &[LatexTokens]) -> Result<Self, Error> {
+		if equations.is_empty() {
+			return Err(Error::NoTokens);
+		}
+		let mut visitor = Variables::default();
+		for tokens in equations {
+			tokens.visit(&mut visitor);
+		}
+		if visitor.0.is_empty() {
+			return Err(Error::NoVariables);
+		}
+		Ok(Self {
+			unknowns: visitor.0,
+			constants: HashMap::new(),
+		})
+	}
+}
+
+impl<Var> ContextLinearSystem<Var> {
+	/// In the order they first appear
+	pub fn unknowns(&self) -> &[Var] {
+		&self.unknowns
+	}
+}
+
+#[test]
+fn subscripts_are_different_variables() {
+	let tokens = LatexTokens::parse_from_latex(r"x_1 + x_2 = 3").unwrap();
+	let Err(Error::MultipleVariables(vars)) = ContextOneVarEq::infer_variable(&tokens) else {
+		panic!("Expected multiple variables");
+	};
+	let subscripted = |subscript: &str| Ident::Subscripted {
+		base: Box::new(Ident::AlphabeticChar('x')),
+		subscript: subscript.to_owned(),
+	};
+	assert_eq!(vars, HashSet::from([subscripted("1"), subscripted("2")]));
+
+	let tokens = LatexTokens::parse_from_latex(r"2 x_1 = x_1 + 3").unwrap();
+	let context = ContextOneVarEq::infer_variable(&tokens).unwrap();
+	assert_eq!(context.solve_for, subscripted("1"));
+}
+
+#[test]
+fn bound_variables_are_not_inferred() {
+	// `i` is bound by the sum, so only `x` is a variable
+	let tokens = LatexTokens::parse_from_latex(r"\sum_{i=1}^{3} i x = 12").unwrap();
+	let context = ContextOneVarEq::infer_variable(&tokens).unwrap();
+	assert_eq!(context.solve_for, Ident::AlphabeticChar('x'));
+}
+
+#[test]
+fn storage_starts_with_the_equation() {
+	let mut storage = RealScalarStorage::from_latext_eq(r"2y = 3".to_owned()).unwrap();
+	assert_eq!(storage.context().solve_for, Ident::AlphabeticChar('y'));
+	let start = storage.storage().start();
+	assert!(start.get::<IsEquation>().is_some());
+	assert_eq!(
+		start.get::<Equation<Ident>>().unwrap().rhs,
+		BigUint::from(3u32).into()
+	);
+
+	assert_eq!(
+		storage.solve().unwrap(),
+		pass::eqs::Solution::Value(num::BigRational::new(3.into(), 2.into()))
+	);
+
+	let Err(Error::NoEquals) = RealScalarStorage::from_latext_eq(r"2y".to_owned()) else {
+		panic!("Expected an expression without = to error");
+	};
+}
+
 #[derive(Clone)]
 pub enum VariableStatus {
 	SolveFor,
 	Constant(ConstantNum),
 }
 
-impl<Var> ContextOneVarEq<Var>
+/// How a context treats each identifier in an expression
+pub trait LookupIdent<Var> {
+	/// `None` if it isn't defined
+	fn lookup_ident(&self, ident: Var) -> Option<VariableStatus>;
+}
+
+impl<Var> LookupIdent<Var> for ContextOneVarEq<Var>
 where
 	Var: PartialEq + Eq + Hash,
 {
-	pub fn lookup_ident(&self, ident: Var) -> Option<VariableStatus> {
+	fn lookup_ident(&self, ident: Var) -> Option<VariableStatus> {
 		if ident == self.solve_for {
 			Some(VariableStatus::SolveFor)
 		} else {
@@ -188,12 +447,29 @@ where
 	}
 }
 
+impl<Var> LookupIdent<Var> for ContextLinearSystem<Var>
+where
+	Var: PartialEq + Eq + Hash,
+{
+	fn lookup_ident(&self, ident: Var) -> Option<VariableStatus> {
+		if self.unknowns.contains(&ident) {
+			Some(VariableStatus::SolveFor)
+		} else {
+			self
+				.constants
+				.get(&ident)
+				.cloned()
+				.map(VariableStatus::Constant)
+		}
+	}
+}
+
 pub mod pass {
 	use crate::prelude::*;
 
-	pub mod eqs {
-		use crate::prelude::*;
-
-		pub fn single_mul_divide_to_solve() {}
-	}
+	pub mod eqs;
+	mod expand;
+	pub mod polynomial;
+	pub mod simplify;
+	pub mod system;
 }
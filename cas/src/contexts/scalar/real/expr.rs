@@ -1,36 +1,47 @@
 use bevy_ecs::component::Component;
-use num::BigUint;
+use num::{BigInt, BigRational, BigUint, Zero};
 
 use super::Ident;
 
-pub struct Exprs<Ident>(pub Vec<Expr<Ident>>);
-
-impl<Ident> IntoIterator for Exprs<Ident> {
-	type Item = Expr<Ident>;
-	type IntoIter = std::vec::IntoIter<Self::Item>;
-
-	fn into_iter(self) -> Self::IntoIter {
-		self.0.into_iter()
-	}
-}
-
-/// Encodes associativity of addition and multiplication
+/// Encodes associativity of addition and multiplication,
+/// negation is multiplying by a negative [`ConstantNum`]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr<Ident> {
 	Constant(ConstantNum),
 	Ident(Ident),
-	Unary(UnaryOp<Ident>),
 	Ops(Ops<Ident>),
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ConstantNum {
-	Positive(BigUint),
+	/// Exact, always in lowest terms with a positive denominator
+	/// so equal numbers compare equal
+	Rational(BigRational),
 	Tau,
-	// Negative(BigUint),
-	// Ratio {
-	//   num: BigUint,
-	//   denom: BigUint
-	// }
+}
+
+impl ConstantNum {
+	pub fn integer(value: impl Into<BigInt>) -> Self {
+		ConstantNum::Rational(BigRational::from_integer(value.into()))
+	}
+
+	/// `None` when dividing by zero or either side is [`ConstantNum::Tau`]
+	pub fn checked_div(&self, denominator: &ConstantNum) -> Option<ConstantNum> {
+		match (self, denominator) {
+			(ConstantNum::Rational(numerator), ConstantNum::Rational(denominator))
+				if !denominator.is_zero() =>
+			{
+				Some(ConstantNum::Rational(numerator / denominator))
+			}
+			_ => None,
+		}
+	}
+}
+
+impl From<BigRational> for ConstantNum {
+	fn from(value: BigRational) -> Self {
+		ConstantNum::Rational(value)
+	}
 }
 
 impl<Var> From<ConstantNum> for Expr<Var> {
@@ -41,7 +52,7 @@ impl<Var> From<ConstantNum> for Expr<Var> {
 
 impl<Var> From<BigUint> for Expr<Var> {
 	fn from(value: BigUint) -> Self {
-		Expr::Constant(ConstantNum::Positive(value))
+		Expr::Constant(ConstantNum::integer(value))
 	}
 }
 
@@ -57,13 +68,10 @@ impl<Var> From<Ops<Var>> for Expr<Var> {
 	}
 }
 
-pub enum UnaryOp<Var> {
-	Neg(Box<Expr<Var>>),
-}
-
+#[derive(Debug, Clone, PartialEq)]
 pub enum Ops<Var> {
 	Add {
-		epxrs: Vec<Expr<Var>>,
+		exprs: Vec<Expr<Var>>,
 		// lhs: Box<Expr<Var>>,
 		// rhs: Box<Expr<Var>>,
 	},
@@ -82,8 +90,8 @@ pub enum Ops<Var> {
 	},
 }
 
-#[derive(Component)]
+#[derive(Component, Debug, Clone, PartialEq)]
 pub struct Equation<Var> {
-	lhs: Expr<Var>,
-	rhs: Expr<Var>,
+	pub lhs: Expr<Var>,
+	pub rhs: Expr<Var>,
 }
@@ -1,3 +1,5 @@
+use latex_parser::{RelationKind, Spanned};
+
 /// Both binary and unary
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OpKind {
@@ -8,28 +10,307 @@ pub enum OpKind {
 	Exp,
 }
 
+/// Expressions separated by relations,
+/// e.g. `a = b < c`
+#[derive(Debug)]
+pub struct Relations<T> {
+	pub first: T,
+	pub pairs: Vec<(Spanned<RelationKind>, T)>,
+}
+
+impl<T> Relations<T> {
+	pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Relations<U> {
+		Relations {
+			first: f(self.first),
+			pairs: self
+				.pairs
+				.into_iter()
+				.map(|(relation, expr)| (relation, f(expr)))
+				.collect(),
+		}
+	}
+
+	pub fn try_map<U, E>(self, mut f: impl FnMut(T) -> Result<U, E>) -> Result<Relations<U>, E> {
+		Ok(Relations {
+			first: f(self.first)?,
+			pairs: self
+				.pairs
+				.into_iter()
+				.map(|(relation, expr)| Ok((relation, f(expr)?)))
+				.collect::<Result<_, E>>()?,
+		})
+	}
+}
+
 pub use ir1::IR1Expr;
 pub use ir2::IR2Exprs;
 pub use ir3::IR3Expr;
 
+#[cfg(test)]
+pub(crate) use from_ir2::parse_one_var;
+
 mod ir1;
 mod ir2;
 mod ir3;
 mod from_ir2 {
 	//! non trivial
 
-	use latex_parser::Ident;
+	use latex_parser::{Ident, LatexTokens, RelationKind};
+	use num::BigRational;
 
 	use crate::{
-		contexts::scalar::real::{ContextOneVarEq, Error, expr::Exprs},
+		contexts::scalar::real::{
+			Error, LookupIdent, VariableStatus,
+			expr::{ConstantNum, Equation, Expr, Ops},
+		},
 		prelude::*,
 	};
 
-	use super::{IR2Exprs, IR3Expr};
+	use super::{
+		IR1Expr, IR2Exprs, IR3Expr,
+		ir3::{IR3BinaryOp, IR3Flat},
+	};
 
-	impl Exprs<Ident> {
-		fn from_ir3(context: &ContextOneVarEq<Ident>, irr: IR3Expr<Ident>) -> Result<Self, Error> {
-			todo!()
+	impl Expr<Ident> {
+		/// Flattens chains like `a + (b + c)` into one [`Ops::Add`],
+		/// and the same for [`Ops::Mul`]
+		pub fn from_ir3(context: &impl LookupIdent<Ident>, ir3: IR3Expr<Ident>) -> Result<Self, Error> {
+			let lower = |expr: Box<IR3Expr<Ident>>| Expr::from_ir3(context, *expr);
+			match ir3 {
+				IR3Expr::Flat(flat) => Expr::from_ir3_flat(context, flat),
+				IR3Expr::BinaryOp(IR3BinaryOp::Add { lhs, rhs }) => Ok(
+					Ops::Add {
+						exprs: flatten([lower(lhs)?, lower(rhs)?], split_add),
+					}
+					.into(),
+				),
+				IR3Expr::BinaryOp(IR3BinaryOp::Mul { lhs, rhs })
+					if matches!(*lhs, IR3Expr::Flat(IR3Flat::Neg1)) =>
+				{
+					// a negative number like `-3` rather than -1 * 3
+					match lower(rhs)? {
+						Expr::Constant(ConstantNum::Rational(rational)) => {
+							Ok(ConstantNum::Rational(-rational).into())
+						}
+						rhs => Ok(
+							Ops::Mul {
+								exprs: flatten([ConstantNum::integer(-1).into(), rhs], split_mul),
+							}
+							.into(),
+						),
+					}
+				}
+				IR3Expr::BinaryOp(IR3BinaryOp::Mul { lhs, rhs }) => Ok(
+					Ops::Mul {
+						exprs: flatten([lower(lhs)?, lower(rhs)?], split_mul),
+					}
+					.into(),
+				),
+				IR3Expr::BinaryOp(IR3BinaryOp::Div { lhs, rhs }) => {
+					let (numerator, denominator) = (lower(lhs)?, lower(rhs)?);
+					// e.g. decimals, which are a division by a power of ten
+					if let (Expr::Constant(numerator), Expr::Constant(denominator)) =
+						(&numerator, &denominator)
+						&& let Some(ratio) = numerator.checked_div(denominator)
+					{
+						return Ok(ratio.into());
+					}
+					Ok(
+						Ops::Div {
+							numerator: Box::new(numerator),
+							denominator: Box::new(denominator),
+						}
+						.into(),
+					)
+				}
+				IR3Expr::BinaryOp(IR3BinaryOp::Exp { base, exponent }) => Ok(
+					Ops::Exp {
+						base: Box::new(lower(base)?),
+						exponent: Box::new(lower(exponent)?),
+					}
+					.into(),
+				),
+			}
 		}
+
+		fn from_ir3_flat(
+			context: &impl LookupIdent<Ident>,
+			flat: IR3Flat<Ident>,
+		) -> Result<Self, Error> {
+			match flat {
+				IR3Flat::Neg1 => Ok(ConstantNum::integer(-1).into()),
+				IR3Flat::Num(num) => Ok(num.into()),
+				// pi = tau / 2
+				IR3Flat::Ident(Ident::Pi) => Ok(
+					Ops::Div {
+						numerator: Box::new(ConstantNum::Tau.into()),
+						denominator: Box::new(BigUint::from(2u32).into()),
+					}
+					.into(),
+				),
+				IR3Flat::Ident(Ident::Tau) => Ok(ConstantNum::Tau.into()),
+				IR3Flat::Ident(ident) => match context.lookup_ident(ident.clone()) {
+					Some(VariableStatus::SolveFor) => Ok(ident.into()),
+					Some(VariableStatus::Constant(constant)) => Ok(constant.into()),
+					None => Err(Error::UndefinedIdent(ident)),
+				},
+				// brackets only group, which is already in the tree
+				IR3Flat::Bracket(expr) => Expr::from_ir3(context, *expr),
+			}
+		}
+	}
+
+	/// Splices in the operands of any `expr` which `split` says is the same operation
+	fn flatten<Var>(
+		exprs: impl IntoIterator<Item = Expr<Var>>,
+		split: impl Fn(Expr<Var>) -> Result<Vec<Expr<Var>>, Expr<Var>>,
+	) -> Vec<Expr<Var>> {
+		let mut flattened = Vec::new();
+		for expr in exprs {
+			match split(expr) {
+				Ok(exprs) => flattened.extend(exprs),
+				Err(expr) => flattened.push(expr),
+			}
+		}
+		flattened
+	}
+
+	fn split_add<Var>(expr: Expr<Var>) -> Result<Vec<Expr<Var>>, Expr<Var>> {
+		match expr {
+			Expr::Ops(Ops::Add { exprs }) => Ok(exprs),
+			expr => Err(expr),
+		}
+	}
+
+	fn split_mul<Var>(expr: Expr<Var>) -> Result<Vec<Expr<Var>>, Expr<Var>> {
+		match expr {
+			Expr::Ops(Ops::Mul { exprs }) => Ok(exprs),
+			expr => Err(expr),
+		}
+	}
+
+	impl Equation<Ident> {
+		/// Exactly one `=` is allowed, with something on both sides
+		pub fn from_latex(
+			context: &impl LookupIdent<Ident>,
+			tokens: LatexTokens,
+		) -> Result<Self, Error> {
+			let relations = IR1Expr::from_latex_tokens(tokens)?;
+			let mut pairs = relations.pairs.into_iter();
+			let Some((relation, rhs)) = pairs.next() else {
+				return Err(Error::NoEquals);
+			};
+			if let Some((relation, _expr)) = pairs.next() {
+				return Err(Error::MultipleRelations {
+					span: relation.span,
+				});
+			}
+			if relation.value != RelationKind::Eq {
+				return Err(Error::UnsupportedRelation {
+					span: relation.span,
+				});
+			}
+
+			let lower = |ir1| {
+				let ir3 = IR3Expr::from_ir2(IR2Exprs::from_ir1(ir1)?);
+				Expr::from_ir3(context, ir3)
+			};
+			Ok(Equation {
+				lhs: lower(relations.first)?,
+				rhs: lower(rhs)?,
+			})
+		}
+	}
+
+	/// Parses `latex` as an equation in the one variable it uses
+	#[cfg(test)]
+	pub(crate) fn parse_one_var(
+		latex: &str,
+	) -> Result<
+		(
+			crate::contexts::scalar::real::ContextOneVarEq<Ident>,
+			Equation<Ident>,
+		),
+		Error,
+	> {
+		let tokens = LatexTokens::parse_from_latex(latex)?;
+		let context = crate::contexts::scalar::real::ContextOneVarEq::infer_variable(&tokens)?;
+		let eq = Equation::from_latex(&context, tokens)?;
+		Ok((context, eq))
+	}
+
+	#[test]
+	fn lowers_to_expr() {
+		let lower = |latex| parse_one_var(latex).map(|(_context, eq)| eq);
+		let x = || Expr::Ident(Ident::AlphabeticChar('x'));
+		let num = |num: u32| Expr::from(BigUint::from(num));
+		let mul = |exprs| Expr::Ops(Ops::Mul { exprs });
+		let add = |exprs| Expr::Ops(Ops::Add { exprs });
+		let neg1 = || Expr::from(ConstantNum::integer(-1));
+
+		assert_eq!(
+			lower(r"2x + 3x - x = 4 \cdot x \cdot 2").unwrap(),
+			Equation {
+				lhs: add(vec![
+					mul(vec![num(2), x()]),
+					mul(vec![num(3), x()]),
+					mul(vec![neg1(), x()]),
+				]),
+				rhs: mul(vec![num(4), x(), num(2)]),
+			}
+		);
+		// brackets only group, so associative chains are flattened through them
+		assert_eq!(
+			lower(r"\left(x + 1\right) + 2 = \frac{x}{\pi}").unwrap(),
+			Equation {
+				lhs: add(vec![x(), num(1), num(2)]),
+				rhs: Expr::Ops(Ops::Div {
+					numerator: Box::new(x()),
+					denominator: Box::new(Expr::Ops(Ops::Div {
+						numerator: Box::new(ConstantNum::Tau.into()),
+						denominator: Box::new(num(2)),
+					})),
+				}),
+			}
+		);
+
+		assert!(matches!(lower(r"2x + 1"), Err(Error::NoEquals)));
+		let Err(Error::UnsupportedRelation { span }) = lower(r"x \le 2") else {
+			panic!("Expected an inequality to error");
+		};
+		assert_eq!(span, latex_parser::Span::new(2, 5));
+		let Err(Error::MultipleRelations { span }) = lower(r"x = 1 = 2") else {
+			panic!("Expected a second = to error");
+		};
+		assert_eq!(span, latex_parser::Span::new(6, 7));
+	}
+
+	#[test]
+	fn folds_constant_division() {
+		let rhs = |latex| parse_one_var(latex).unwrap().1.rhs;
+		let ratio = |numerator: i32, denominator: i32| {
+			Expr::from(ConstantNum::from(BigRational::new(
+				numerator.into(),
+				denominator.into(),
+			)))
+		};
+		assert_eq!(rhs(r"2y = \frac{6}{4}"), ratio(3, 2));
+		assert_eq!(rhs(r"2y = 0.25"), ratio(1, 4));
+		// the -1 from negating is folded too
+		assert_eq!(rhs(r"2y = \frac{-1}{-2}"), ratio(1, 2));
+		assert_eq!(
+			rhs(r"2y = \frac{\frac{1}{2}}{3}"),
+			Expr::from(ConstantNum::from(BigRational::new(1.into(), 6.into())))
+		);
+
+		// not a number
+		assert!(matches!(
+			rhs(r"2y = \frac{1}{0}"),
+			Expr::Ops(Ops::Div { .. })
+		));
+		assert!(matches!(
+			rhs(r"2y = \frac{\tau}{2}"),
+			Expr::Ops(Ops::Div { .. })
+		));
 	}
 }
@@ -1,17 +1,23 @@
-use latex_parser::{Bracketed, Frac, Ident, LatexToken};
+use latex_parser::{
+	Bracketed, Decimal, Frac, Function, Ident, LatexToken, RelationKind, Root, Spanned,
+};
 use num::BigUint;
 
 use crate::contexts::scalar::real::Error;
 
 use super::*;
 
-/// Removes [`LatexToken::Eq`],
-/// normalizes [`LatexToken::Frac`] and [`LatexToken::Exp`],
+/// Decimals with a bigger power of ten than this are rejected,
+/// since the exact number would take up too much memory
+const MAX_DECIMAL_EXPONENT: u32 = 1000;
+
+/// Removes [`LatexToken::Relation`]s,
+/// normalizes [`LatexToken::Frac`], [`LatexToken::Root`], [`LatexToken::Decimal`] and [`LatexToken::Exp`],
 /// hasn't handled operator precedence yet
 #[derive(Debug)]
 pub enum IR1Expr {
-	Op(OpKind),
-	Expr(IR1Flat),
+	Op(Spanned<OpKind>),
+	Expr(Spanned<IR1Flat>),
 }
 
 #[derive(Debug)]
@@ -24,75 +30,181 @@ pub enum IR1Flat {
 
 impl From<OpKind> for IR1Expr {
 	fn from(op: OpKind) -> Self {
+		IR1Expr::Op(op.into())
+	}
+}
+
+impl From<Spanned<OpKind>> for IR1Expr {
+	fn from(op: Spanned<OpKind>) -> Self {
 		IR1Expr::Op(op)
 	}
 }
 
 impl From<IR1Flat> for IR1Expr {
 	fn from(token: IR1Flat) -> Self {
+		IR1Expr::Expr(token.into())
+	}
+}
+
+impl From<Spanned<IR1Flat>> for IR1Expr {
+	fn from(token: Spanned<IR1Flat>) -> Self {
 		IR1Expr::Expr(token)
 	}
 }
 
 impl IR1Expr {
-	/// Calls [Self::from_latex_tokens] and errors if there is any eq nesting
-	fn from_latex_nested(
-		tokens: impl IntoIterator<Item = LatexToken>,
-	) -> Result<Vec<IR1Expr>, Error> {
-		let inner = Self::from_latex_tokens(tokens)?;
-		if inner.len() != 1 {
-			return Err(Error::CantNestEq);
+	/// Calls [Self::from_latex_tokens] and errors if there is any relation nesting
+	fn from_latex_nested(tokens: Vec<Spanned<LatexToken>>) -> Result<Vec<IR1Expr>, Error> {
+		if let Some(relation) = tokens
+			.iter()
+			.find(|token| matches!(token.value, LatexToken::Relation(_)))
+		{
+			return Err(Error::CantNestEq {
+				span: relation.span,
+			});
 		}
-		Ok(inner.into_iter().next().unwrap())
+		let inner = Self::from_latex_tokens(tokens)?;
+		Ok(inner.first)
 	}
 
-	/// Split by [`LatexToken::Eq`].
-	/// Therefore, len should be num of equals signs - 1.
+	/// Split by [`LatexToken::Relation`]s,
+	/// remembering which relation was between each expression.
 	///
 	/// Assumes all brackets are equal
 	pub fn from_latex_tokens(
-		tokens: impl IntoIterator<Item = LatexToken>,
-	) -> Result<Vec<Vec<IR1Expr>>, Error> {
+		tokens: impl IntoIterator<Item = Spanned<LatexToken>>,
+	) -> Result<Relations<Vec<IR1Expr>>, Error> {
+		let mut relations: Vec<Spanned<RelationKind>> = Vec::new();
 		let mut ret: Vec<Vec<IR1Expr>> = Vec::new();
 		// starts with something
 		// which is the condition for .last_mut() to be unwrapped
 		ret.push(vec![]);
 
-		for token in tokens {
+		for Spanned { span, value: token } in tokens {
 			let current = ret.last_mut().unwrap();
+			let op = |op: OpKind| IR1Expr::Op(Spanned::new(op, span));
+			let flat = |flat: IR1Flat| IR1Expr::Expr(Spanned::new(flat, span));
 			match token {
-				LatexToken::Eq => {
+				LatexToken::Relation(relation) => {
+					relations.push(Spanned::new(relation, span));
 					ret.push(Vec::new());
 					continue;
 				}
-				LatexToken::Neg => current.push(OpKind::Neg.into()),
-				LatexToken::Mul => current.push(OpKind::Mul.into()),
-				LatexToken::Add => current.push(OpKind::Add.into()),
+				LatexToken::Neg => current.push(op(OpKind::Neg)),
+				LatexToken::Mul => current.push(op(OpKind::Mul)),
+				LatexToken::Add => current.push(op(OpKind::Add)),
 				LatexToken::Exp(tokens) => {
-					current.push(OpKind::Exp.into());
+					current.push(op(OpKind::Exp));
 					if !tokens.is_empty() {
-						current.push(IR1Flat::Bracketed(IR1Expr::from_latex_nested(tokens)?).into());
+						current.push(flat(IR1Flat::Bracketed(IR1Expr::from_latex_nested(
+							tokens,
+						)?)));
 					}
 				}
-				LatexToken::Ident(ident) => current.push(IR1Flat::Ident(ident).into()),
-				LatexToken::Num(num) => current.push(IR1Flat::Num(num).into()),
-				LatexToken::Bracketed(Bracketed {
-					bracket: latex_parser::Bracket::Round,
-					inner,
-				}) => {
-					// transform only
-					current.push(IR1Flat::Bracketed(IR1Expr::from_latex_nested(inner)?).into());
+				LatexToken::Ident(ident) => current.push(flat(IR1Flat::Ident(ident))),
+				LatexToken::Num(num) => current.push(flat(IR1Flat::Num(num))),
+				LatexToken::Decimal(Decimal { mantissa, exponent }) => {
+					// exactly mantissa * 10^exponent
+					let power = u32::try_from(exponent.unsigned_abs())
+						.ok()
+						.filter(|power| *power <= MAX_DECIMAL_EXPONENT)
+						.ok_or(Error::NumberTooLarge { span })?;
+					let power = BigUint::from(10u32).pow(power);
+					if exponent >= 0 {
+						current.push(flat(IR1Flat::Num(mantissa * power)));
+					} else {
+						current.push(flat(IR1Flat::Bracketed(vec![
+							flat(IR1Flat::Num(mantissa)),
+							op(OpKind::Div),
+							flat(IR1Flat::Num(power)),
+						])));
+					}
+				}
+				LatexToken::Bracketed(Bracketed { bracket: _, inner }) => {
+					// all kinds of brackets only group
+					current.push(flat(IR1Flat::Bracketed(IR1Expr::from_latex_nested(inner)?)));
 				}
 				LatexToken::Frac(Frac {
 					numerator,
 					denominator,
 				}) => {
-					current.push(IR1Flat::Bracketed(IR1Expr::from_latex_nested(numerator)?).into());
-					current.push(OpKind::Div.into());
-					current.push(IR1Flat::Bracketed(IR1Expr::from_latex_nested(denominator)?).into())
+					current.push(flat(IR1Flat::Bracketed(IR1Expr::from_latex_nested(
+						numerator,
+					)?)));
+					current.push(op(OpKind::Div));
+					current.push(flat(IR1Flat::Bracketed(IR1Expr::from_latex_nested(
+						denominator,
+					)?)));
+				}
+				LatexToken::Root(Root { index, radicand }) => {
+					// radicand ^ (1 / index)
+					let index = match index {
+						Some(index) => IR1Flat::Bracketed(IR1Expr::from_latex_nested(index)?),
+						None => IR1Flat::Num(2u32.into()),
+					};
+					current.push(flat(IR1Flat::Bracketed(IR1Expr::from_latex_nested(
+						radicand,
+					)?)));
+					current.push(op(OpKind::Exp));
+					current.push(flat(IR1Flat::Bracketed(vec![
+						flat(IR1Flat::Num(1u32.into())),
+						op(OpKind::Div),
+						flat(index),
+					])));
+				}
+				// units and labels don't change the maths
+				LatexToken::Text(_) => {}
+				LatexToken::Error(span) => {
+					return Err(Error::InvalidLatex { span });
+				}
+				LatexToken::BigOp(_) | LatexToken::Integral(_) | LatexToken::Limit(_) => {
+					return Err(Error::UnsupportedCalculus { span });
+				}
+				LatexToken::Matrix(_) => {
+					return Err(Error::UnsupportedMatrix { span });
+				}
+				LatexToken::Abs(_) => {
+					return Err(Error::UnsupportedAbs { span });
+				}
+				LatexToken::Function(Function { name, .. }) => {
+					return Err(Error::UnsupportedFunction { name, span });
 				}
 			}
 		}
-		Ok(ret)
+		let mut ret = ret.into_iter();
+		let first = ret.next().unwrap();
+		Ok(Relations {
+			first,
+			pairs: relations.into_iter().zip(ret).collect(),
+		})
 	}
 }
+
+#[test]
+fn split_by_relations() {
+	let tokens = latex_parser::LatexTokens::parse_from_latex(r"2x + 1 < 5 \le y").unwrap();
+	let relations = IR1Expr::from_latex_tokens(tokens).unwrap();
+	assert_eq!(relations.first.len(), 4);
+	let kinds: Vec<RelationKind> = relations
+		.pairs
+		.iter()
+		.map(|(relation, _expr)| relation.value)
+		.collect();
+	assert_eq!(kinds, vec![RelationKind::Lt, RelationKind::Le]);
+
+	let tokens = latex_parser::LatexTokens::parse_from_latex(r"\frac{x = 2}{3}").unwrap();
+	let Err(Error::CantNestEq { span }) = IR1Expr::from_latex_tokens(tokens) else {
+		panic!("Expected an error");
+	};
+	assert_eq!(span, latex_parser::Span::new(8, 9));
+}
+
+#[test]
+fn text_is_ignored() {
+	let with_units = latex_parser::LatexTokens::parse_from_latex(r"2x = 4\text{m}").unwrap();
+	let without = latex_parser::LatexTokens::parse_from_latex(r"2x = 4").unwrap();
+	assert_eq!(
+		format!("{:?}", IR1Expr::from_latex_tokens(with_units).unwrap()),
+		format!("{:?}", IR1Expr::from_latex_tokens(without).unwrap())
+	);
+}
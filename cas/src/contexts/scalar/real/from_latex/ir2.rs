@@ -2,7 +2,7 @@ use std::iter::Peekable;
 
 use crate::{contexts::scalar::real::Error, prelude::*};
 
-use latex_parser::Ident;
+use latex_parser::{Ident, Spanned};
 
 use super::{
 	OpKind,
@@ -82,25 +82,33 @@ where
 {
 	let first = tokens.next().ok_or(Error::NoTokens)?;
 	match first {
-		IR1Expr::Expr(expr) => Ok(ResolvedExpr::Single(IR2Flat::from_ir1(expr)?)),
+		IR1Expr::Expr(expr) => Ok(ResolvedExpr::Single(IR2Flat::from_ir1(expr.value)?)),
 		// since this is the first, must be an unary
-		IR1Expr::Op(op) => match op {
+		IR1Expr::Op(op) => match op.value {
 			// these can act as unary
 			OpKind::Add | OpKind::Neg => {
 				// handle +-++-- cancelling
-				let mut current = Sign::from_op(op);
-				while let Some(&IR1Expr::Op(OpKind::Add | OpKind::Neg)) = tokens.peek() {
+				let mut current = Sign::from_op(op.value);
+				let mut last = op;
+				while let Some(IR1Expr::Op(Spanned {
+					value: OpKind::Add | OpKind::Neg,
+					..
+				})) = tokens.peek()
+				{
 					let Some(IR1Expr::Op(next_op)) = tokens.next() else {
 						unreachable!()
 					};
-					current = current.combine(Sign::from_op(next_op));
+					current = current.combine(Sign::from_op(next_op.value));
+					last = next_op;
 				}
 
 				// must be basic expr next
-				let IR1Expr::Expr(flat) = tokens.next().ok_or(Error::NoTokens)? else {
-					return Err(Error::CantListOperators);
+				let flat = match tokens.next() {
+					Some(IR1Expr::Expr(flat)) => flat,
+					Some(IR1Expr::Op(op)) => return Err(Error::CantListOperators { span: op.span }),
+					None => return Err(Error::CantEndOnAddNeg { span: last.span }),
 				};
-				let flat = IR2Flat::from_ir1(flat)?;
+				let flat = IR2Flat::from_ir1(flat.value)?;
 				match current {
 					Sign::Positive => Ok(ResolvedExpr::Single(flat)),
 					Sign::Negative => {
@@ -110,7 +118,7 @@ where
 				}
 			}
 			// these can't act as unary
-			OpKind::Mul | OpKind::Div | OpKind::Exp => Err(Error::CantListOperators),
+			OpKind::Mul | OpKind::Div | OpKind::Exp => Err(Error::CantListOperators { span: op.span }),
 		},
 	}
 }
@@ -153,14 +161,14 @@ fn cancelling_add_neg() {
 fn resolve_op<I>(
 	tokens: &mut Peekable<I>,
 	pairs: &mut Vec<(OpKind, IR2Flat)>,
-) -> Result<Option<OpKind>, Error>
+) -> Result<Option<Spanned<OpKind>>, Error>
 where
 	I: Iterator<Item = IR1Expr>,
 {
 	match tokens.next().ok_or(Error::NoTokens)? {
 		IR1Expr::Op(op) => Ok(Some(op)),
 		IR1Expr::Expr(flat) => {
-			let flat = IR2Flat::from_ir1(flat)?;
+			let flat = IR2Flat::from_ir1(flat.value)?;
 			pairs.push((OpKind::Mul, flat));
 			if tokens.peek().is_some() {
 				resolve_op(tokens, pairs)
@@ -202,7 +210,7 @@ fn op_implicit_mul_twice() {
 			(OpKind::Mul, IR2Flat::Num(42u32.into()))
 		]
 	);
-	assert_eq!(resolved, Some(OpKind::Div));
+	assert_eq!(resolved, Some(OpKind::Div.into()));
 }
 
 impl IR2Exprs {
@@ -229,20 +237,86 @@ impl IR2Exprs {
 			let Some(op) = op else {
 				continue;
 			};
+			if tokens.peek().is_none() && matches!(op.value, OpKind::Add | OpKind::Neg) {
+				return Err(Error::CantEndOnAddNeg { span: op.span });
+			}
 
 			// expr
-			let expr = resolve_expr(&mut tokens)?;
-			let expr = match expr {
-				ResolvedExpr::Single(flat) => flat,
-				ResolvedExpr::Negated(one, op, two) => {
-					pairs.push((op, two));
-					one
+			match resolve_expr(&mut tokens)? {
+				ResolvedExpr::Single(flat) => pairs.push((op.value, flat)),
+				ResolvedExpr::Negated(neg1, mul, flat) => {
+					pairs.extend(negated_pairs(op.value, neg1, mul, flat))
 				}
-			};
-
-			pairs.push((op, expr));
+			}
 		}
 
 		Ok(IR2Exprs { first, pairs })
 	}
 }
+
+/// `op -flat`, so the `-1` binds like `flat` would have,
+/// e.g. `a - -b` is `a + b` and `6 / -3` is `6 / -1 / 3`
+fn negated_pairs(op: OpKind, neg1: IR2Flat, mul: OpKind, flat: IR2Flat) -> Vec<(OpKind, IR2Flat)> {
+	match op {
+		OpKind::Add => vec![(OpKind::Neg, flat)],
+		OpKind::Neg => vec![(OpKind::Add, flat)],
+		OpKind::Mul | OpKind::Div => vec![(op, neg1), (op, flat)],
+		// exponents are always bracketed, but just in case
+		OpKind::Exp => vec![(
+			op,
+			IR2Flat::Bracketed(Box::new(IR2Exprs {
+				first: neg1,
+				pairs: vec![(mul, flat)],
+			})),
+		)],
+	}
+}
+
+#[test]
+fn negating_after_an_operator() {
+	let ir2 = |latex| {
+		let tokens = latex_parser::LatexTokens::parse_from_latex(latex).unwrap();
+		IR2Exprs::from_ir1(IR1Expr::from_latex_tokens(tokens).unwrap().first).unwrap()
+	};
+	let a = || IR2Flat::Ident(Ident::AlphabeticChar('a'));
+	let b = || IR2Flat::Ident(Ident::AlphabeticChar('b'));
+
+	assert_eq!(ir2(r"a - -b").pairs, vec![(OpKind::Add, b())]);
+	assert_eq!(ir2(r"a + -b").pairs, vec![(OpKind::Neg, b())]);
+	// latex has no `/`, but lowering adds it
+	let tokens: Vec<IR1Expr> = vec![
+		IR1Flat::Num(6u32.into()).into(),
+		OpKind::Div.into(),
+		OpKind::Neg.into(),
+		IR1Flat::Num(3u32.into()).into(),
+	];
+	assert_eq!(
+		IR2Exprs::from_ir1(tokens).unwrap().pairs,
+		vec![
+			(OpKind::Div, IR2Flat::Neg1),
+			(OpKind::Div, IR2Flat::Num(3u32.into()))
+		]
+	);
+	assert_eq!(ir2(r"a \cdot -b").first, a());
+	assert_eq!(
+		ir2(r"a \cdot -b").pairs,
+		vec![(OpKind::Mul, IR2Flat::Neg1), (OpKind::Mul, b())]
+	);
+}
+
+#[test]
+fn errors_point_at_operator() {
+	let tokens = latex_parser::LatexTokens::parse_from_latex(r"2 + 3 -").unwrap();
+	let ir1 = IR1Expr::from_latex_tokens(tokens).unwrap().first;
+	let Err(err) = IR2Exprs::from_ir1(ir1) else {
+		panic!("Expected an error");
+	};
+	assert_eq!(err.span(), Some(latex_parser::Span::new(6, 7)));
+
+	let tokens = latex_parser::LatexTokens::parse_from_latex(r"2 \cdot \cdot 3").unwrap();
+	let ir1 = IR1Expr::from_latex_tokens(tokens).unwrap().first;
+	let Err(Error::CantListOperators { span }) = IR2Exprs::from_ir1(ir1) else {
+		panic!("Expected an error");
+	};
+	assert_eq!(span, latex_parser::Span::new(8, 13));
+}
@@ -101,24 +101,25 @@ impl OpKind {
 }
 
 impl IR3Expr<Ident> {
-	fn recursive_from_ir2(
-		prev: IR3Expr<Ident>,
-		op1: OpKind,
-		current: IR3Flat<Ident>,
-		mut next: Peekable<impl Iterator<Item = (OpKind, IR3Flat<Ident>)>>,
+	/// Precedence climbing, so `a - b c + d` is `(a - (b c)) + d`.
+	///
+	/// Takes pairs while their operator is at least `min_precedence`,
+	/// grouping any higher precedence operators after them first
+	fn climb(
+		mut lhs: IR3Expr<Ident>,
+		min_precedence: NonZero<u8>,
+		pairs: &mut Peekable<impl Iterator<Item = (OpKind, IR3Flat<Ident>)>>,
 	) -> IR3Expr<Ident> {
-		let Some((op2, next_expr)) = next.next() else {
-			// base case, ended
-			return IR3BinaryOp::new(prev, op1, current.into()).into();
-		};
-
-		if op1.is_higher_or_eq_precedence(op2) {
-			let lhs = IR3BinaryOp::new(prev, op1, current.into()).into();
-			IR3Expr::recursive_from_ir2(lhs, op2, next_expr, next)
-		} else {
-			let rhs = IR3Expr::recursive_from_ir2(current.into(), op2, next_expr, next);
-			IR3BinaryOp::new(prev, op1, rhs).into()
+		while let Some((op, rhs)) = pairs.next_if(|(op, _rhs)| op.precedence() >= min_precedence) {
+			let mut rhs = rhs.into();
+			while let Some((next_op, _next_rhs)) = pairs.peek()
+				&& !op.is_higher_or_eq_precedence(*next_op)
+			{
+				rhs = IR3Expr::climb(rhs, next_op.precedence(), pairs);
+			}
+			lhs = IR3BinaryOp::new(lhs, op, rhs).into();
 		}
+		lhs
 	}
 
 	pub fn from_ir2(ir2: IR2Exprs) -> Self {
@@ -129,12 +130,7 @@ impl IR3Expr<Ident> {
 			.map(|(op, expr)| (op, IR3Flat::from_ir2(expr)))
 			.peekable();
 
-		let Some((op, expr)) = pairs.next() else {
-			// base case, single expr
-			return first;
-		};
-
-		IR3Expr::recursive_from_ir2(first, op, expr, pairs)
+		IR3Expr::climb(first, u8!(1), &mut pairs)
 	}
 }
 
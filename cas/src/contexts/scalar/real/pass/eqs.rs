@@ -0,0 +1,438 @@
+//! Solving an [`Equation`] for [`ContextOneVarEq::solve_for`],
+//! recording every move made to both sides as a [`Step`]
+
+use std::fmt;
+
+use bevy_ecs::{
+	component::Component,
+	entity::Entity,
+	system::{Commands, In, Query, Res},
+};
+use latex_parser::Ident;
+use num::{BigRational, One, Signed, Zero};
+
+use crate::{
+	contexts::scalar::real::{
+		ContextOneVarEq, Error, Line,
+		expr::{ConstantNum, Equation, Expr, Ops},
+	},
+	prelude::*,
+	storage::Rewrite,
+};
+
+use super::polynomial::{Polynomial, Root, solve_polynomial};
+
+/// `coefficient * solve_for + constant`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Linear {
+	pub coefficient: BigRational,
+	pub constant: BigRational,
+}
+
+impl Linear {
+	/// A [`Polynomial::from_expr`] of degree at most one
+	pub fn from_expr(solve_for: &Ident, expr: &Expr<Ident>) -> Result<Self, Error> {
+		let polynomial = Polynomial::from_expr(solve_for, expr).map_err(|err| match err {
+			Error::NotPolynomial => Error::NotLinear,
+			err => err,
+		})?;
+		if polynomial.degree() > Some(1) {
+			return Err(Error::NotLinear);
+		}
+		Ok(Linear {
+			coefficient: polynomial.coefficient(1),
+			constant: polynomial.coefficient(0),
+		})
+	}
+
+	/// Leaves out zero terms, and a coefficient of one
+	pub fn into_expr(self, solve_for: &Ident) -> Expr<Ident> {
+		let mut terms = Vec::new();
+		if !self.coefficient.is_zero() {
+			let variable = Expr::Ident(solve_for.clone());
+			terms.push(if self.coefficient.is_one() {
+				variable
+			} else {
+				Ops::Mul {
+					exprs: vec![ConstantNum::Rational(self.coefficient).into(), variable],
+				}
+				.into()
+			});
+		}
+		if !self.constant.is_zero() {
+			terms.push(ConstantNum::Rational(self.constant).into());
+		}
+		match <[_; 1]>::try_from(terms) {
+			Ok([term]) => term,
+			Err(terms) if terms.is_empty() => ConstantNum::integer(0).into(),
+			Err(exprs) => Ops::Add { exprs }.into(),
+		}
+	}
+}
+
+/// Something done to both sides of the equation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Move {
+	/// Expanding brackets and collecting like terms on each side
+	Collect,
+	/// Subtracting `coefficient * variable` from both sides
+	SubtractTerm {
+		coefficient: BigRational,
+		variable: Ident,
+	},
+	SubtractConstant(BigRational),
+	DivideBy(BigRational),
+	/// Subtracting the right side from both sides, leaving zero on the right
+	MoveToLeft,
+	/// Factoring `variable - root` out of a polynomial
+	FactorOut {
+		root: BigRational,
+		variable: Ident,
+	},
+	/// Reading the roots of a quadratic factor off the quadratic formula,
+	/// there are none if the `discriminant` is negative
+	QuadraticFormula {
+		discriminant: BigRational,
+	},
+	/// Isolating the real roots of a factor of a higher degree numerically
+	ApproximateRoots,
+}
+
+impl fmt::Display for Move {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		/// Adding a negative is written as subtracting
+		fn add_or_subtract(
+			f: &mut fmt::Formatter<'_>,
+			subtracted: &BigRational,
+			term: impl Fn(&BigRational) -> String,
+		) -> fmt::Result {
+			if subtracted.is_negative() {
+				write!(f, "add {} to both sides", term(&-subtracted))
+			} else {
+				write!(f, "subtract {} from both sides", term(subtracted))
+			}
+		}
+		match self {
+			Move::Collect => write!(f, "expand and collect like terms"),
+			Move::SubtractTerm {
+				coefficient,
+				variable,
+			} => add_or_subtract(f, coefficient, |coefficient| {
+				if coefficient.is_one() {
+					variable.to_string()
+				} else if coefficient.is_integer() {
+					format!("{}{}", coefficient, variable)
+				} else {
+					format!("({}){}", coefficient, variable)
+				}
+			}),
+			Move::SubtractConstant(constant) => add_or_subtract(f, constant, ToString::to_string),
+			Move::DivideBy(divisor) => write!(f, "divide both sides by {}", divisor),
+			Move::MoveToLeft => write!(f, "subtract the right side from both sides"),
+			Move::FactorOut { root, variable } if root.is_zero() => {
+				write!(f, "factor out {}", variable)
+			}
+			Move::FactorOut { root, variable } if root.is_negative() => {
+				write!(f, "factor out ({} + {})", variable, -root)
+			}
+			Move::FactorOut { root, variable } => write!(f, "factor out ({} - {})", variable, root),
+			Move::QuadraticFormula { discriminant } if discriminant.is_negative() => write!(
+				f,
+				"use the quadratic formula, the discriminant {} is negative so there are no real roots",
+				discriminant
+			),
+			Move::QuadraticFormula { discriminant } => write!(
+				f,
+				"use the quadratic formula, the discriminant is {}",
+				discriminant
+			),
+			Move::ApproximateRoots => write!(f, "find the real roots numerically"),
+		}
+	}
+}
+
+/// A [`Move`] and the equation after it,
+/// which is unchanged for moves that only find roots like [`Move::QuadraticFormula`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+	pub r#move: Move,
+	pub equation: Equation<Ident>,
+}
+
+/// Also added by [`solve_steps`] to the line it solved
+#[derive(Component, Debug, Clone, PartialEq)]
+pub enum Solution {
+	/// The only solution of a linear equation
+	Value(BigRational),
+	/// Every real root of a polynomial, in increasing order
+	Roots(Vec<Root>),
+	/// Simplified to something false like `1 = 3`
+	None,
+	/// A polynomial which is never zero, like `x^2 + 1`
+	NoRealRoots,
+	/// Simplified to something always true like `2 = 2`
+	Infinite,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solved {
+	pub steps: Vec<Step>,
+	pub solution: Solution,
+}
+
+/// Isolates `solve_for` on the left,
+/// first moving every `solve_for` term there and every constant to the right
+pub fn solve_linear(
+	context: &ContextOneVarEq<Ident>,
+	eq: &Equation<Ident>,
+) -> Result<Solved, Error> {
+	let solve_for = &context.solve_for;
+	let mut lhs = Linear::from_expr(solve_for, &eq.lhs)?;
+	let mut rhs = Linear::from_expr(solve_for, &eq.rhs)?;
+
+	let mut steps = Vec::new();
+	let mut step = |r#move, lhs: &Linear, rhs: &Linear| {
+		steps.push(Step {
+			r#move,
+			equation: Equation {
+				lhs: lhs.clone().into_expr(solve_for),
+				rhs: rhs.clone().into_expr(solve_for),
+			},
+		})
+	};
+
+	let collected = Equation {
+		lhs: lhs.clone().into_expr(solve_for),
+		rhs: rhs.clone().into_expr(solve_for),
+	};
+	if collected != *eq {
+		step(Move::Collect, &lhs, &rhs);
+	}
+
+	if !rhs.coefficient.is_zero() {
+		let coefficient = std::mem::take(&mut rhs.coefficient);
+		lhs.coefficient -= &coefficient;
+		step(
+			Move::SubtractTerm {
+				coefficient,
+				variable: solve_for.clone(),
+			},
+			&lhs,
+			&rhs,
+		);
+	}
+
+	if lhs.coefficient.is_zero() {
+		let solution = if lhs.constant == rhs.constant {
+			Solution::Infinite
+		} else {
+			Solution::None
+		};
+		return Ok(Solved { steps, solution });
+	}
+
+	if !lhs.constant.is_zero() {
+		let constant = std::mem::take(&mut lhs.constant);
+		rhs.constant -= &constant;
+		step(Move::SubtractConstant(constant), &lhs, &rhs);
+	}
+
+	if !lhs.coefficient.is_one() {
+		let divisor = std::mem::replace(&mut lhs.coefficient, BigRational::one());
+		rhs.constant /= &divisor;
+		step(Move::DivideBy(divisor), &lhs, &rhs);
+	}
+
+	Ok(Solved {
+		steps,
+		solution: Solution::Value(rhs.constant),
+	})
+}
+
+/// With [`solve_linear`] if both sides are linear,
+/// otherwise as a polynomial
+pub fn solve(context: &ContextOneVarEq<Ident>, eq: &Equation<Ident>) -> Result<Solved, Error> {
+	let lhs = Polynomial::from_expr(&context.solve_for, &eq.lhs)?;
+	let rhs = Polynomial::from_expr(&context.solve_for, &eq.rhs)?;
+	if lhs.degree() <= Some(1) && rhs.degree() <= Some(1) {
+		return solve_linear(context, eq);
+	}
+	solve_polynomial(context, eq, lhs, rhs)
+}
+
+/// Every [`Step`] of [`solve`] as a pass, solving only once,
+/// and adding the [`Solution`] to the line it solved.
+///
+/// Steps that only find roots leave the equation unchanged, so aren't lines
+pub fn solve_steps(
+	In(line): In<Entity>,
+	context: Res<ContextOneVarEq<Ident>>,
+	equations: Query<&Equation<Ident>>,
+	mut commands: Commands,
+) -> Result<Vec<Rewrite<Line>>, Error> {
+	let eq = equations.get(line).expect("lines to be equations");
+	let Solved { steps, solution } = solve(&context, eq)?;
+	commands.entity(line).insert(solution);
+
+	let mut previous = eq;
+	let mut rewrites = Vec::new();
+	for step in &steps {
+		if step.equation != *previous {
+			rewrites.push(Rewrite {
+				line: Line::new(step.equation.clone()),
+				description: step.r#move.to_string(),
+			});
+		}
+		previous = &step.equation;
+	}
+	Ok(rewrites)
+}
+
+#[test]
+fn solves_linear() {
+	use crate::contexts::scalar::real::parse_one_var;
+
+	let solve = |latex| {
+		let (context, eq) = parse_one_var(latex).unwrap();
+		solve_linear(&context, &eq)
+	};
+	let moves = |solved: &Solved| {
+		solved
+			.steps
+			.iter()
+			.map(|step| step.r#move.to_string())
+			.collect::<Vec<_>>()
+	};
+	let value = |numerator: i32, denominator: i32| {
+		Solution::Value(BigRational::new(numerator.into(), denominator.into()))
+	};
+
+	let solved = solve(r"2y = 3").unwrap();
+	assert_eq!(moves(&solved), vec!["divide both sides by 2"]);
+	assert_eq!(solved.solution, value(3, 2));
+
+	let solved = solve(r"2x + 1 = 5").unwrap();
+	assert_eq!(
+		moves(&solved),
+		vec!["subtract 1 from both sides", "divide both sides by 2"]
+	);
+	assert_eq!(solved.solution, value(2, 1));
+	assert_eq!(
+		solved.steps.last().unwrap().equation,
+		parse_one_var(r"x = 2").unwrap().1
+	);
+
+	let solved = solve(r"3x - 1 = x + 3").unwrap();
+	assert_eq!(
+		moves(&solved),
+		vec![
+			"subtract x from both sides",
+			"add 1 to both sides",
+			"divide both sides by 2"
+		]
+	);
+	assert_eq!(solved.solution, value(2, 1));
+
+	let solved = solve(r"10 - 2x + 4 = 0").unwrap();
+	assert_eq!(
+		moves(&solved),
+		vec![
+			"expand and collect like terms",
+			"subtract 14 from both sides",
+			"divide both sides by -2"
+		]
+	);
+	assert_eq!(solved.solution, value(7, 1));
+
+	let solved = solve(r"\frac{x}{3} + \frac{1}{2} = 0").unwrap();
+	assert_eq!(solved.solution, value(-3, 2));
+
+	// negating after an operator
+	assert_eq!(solve(r"x = 5 - -3").unwrap().solution, value(8, 1));
+	assert_eq!(solve(r"x = 2 \cdot -3").unwrap().solution, value(-6, 1));
+	assert_eq!(solve(r"x - -2 = 0").unwrap().solution, value(-2, 1));
+	assert_eq!(
+		solve(r"x = 2 \cdot -3^{2}").unwrap().solution,
+		value(-18, 1)
+	);
+
+	assert_eq!(solve(r"x + 1 = x + 2").unwrap().solution, Solution::None);
+	assert_eq!(
+		solve(r"2\left(x + 1\right) = 2x + 2").unwrap().solution,
+		Solution::Infinite
+	);
+
+	assert!(matches!(solve(r"x^2 = 4"), Err(Error::NotLinear)));
+	assert!(matches!(solve(r"\frac{1}{x} = 2"), Err(Error::NotLinear)));
+	assert!(matches!(
+		solve(r"x = \frac{1}{0}"),
+		Err(Error::DivideByZero)
+	));
+	assert!(matches!(
+		solve(r"\pi x = 1"),
+		Err(Error::IrrationalConstant)
+	));
+	assert!(matches!(
+		solve(r"x = 2^{100000}"),
+		Err(Error::PowerTooLarge)
+	));
+	// each exponent is small, but the result isn't
+	assert!(matches!(
+		solve(r"x = \left(\left(10^{1000}\right)^{1000}\right)^{1000}"),
+		Err(Error::PowerTooLarge)
+	));
+}
+
+#[test]
+fn linear_steps_are_lines() {
+	use crate::contexts::scalar::real::RealScalarStorage;
+
+	let mut storage = RealScalarStorage::from_latext_eq(r"3x - 1 = x + 3".to_owned()).unwrap();
+	assert_eq!(
+		storage.solve().unwrap(),
+		Solution::Value(BigRational::from_integer(2.into()))
+	);
+
+	let derivation = storage.storage().derivation();
+	let descriptions: Vec<_> = derivation
+		.iter()
+		.skip(1)
+		.map(|line| {
+			let derived = line.derived().unwrap();
+			assert!(derived.pass.ends_with("solve_steps"), "{}", derived.pass);
+			derived.description.as_str()
+		})
+		.collect();
+	assert_eq!(
+		descriptions,
+		vec![
+			"subtract x from both sides",
+			"add 1 to both sides",
+			"divide both sides by 2"
+		]
+	);
+
+	// solved once, from the first line
+	assert_eq!(
+		derivation[0].get::<Solution>(),
+		Some(&Solution::Value(BigRational::from_integer(2.into())))
+	);
+	assert!(derivation[1].get::<Solution>().is_none());
+
+	// the same steps as solving all at once
+	let start = derivation[0].get::<Equation<Ident>>().unwrap();
+	let solved = solve_linear(storage.context(), start).unwrap();
+	let equations: Vec<_> = derivation
+		.iter()
+		.skip(1)
+		.map(|line| line.get::<Equation<Ident>>().unwrap())
+		.collect();
+	assert_eq!(
+		equations,
+		solved
+			.steps
+			.iter()
+			.map(|step| &step.equation)
+			.collect::<Vec<_>>()
+	);
+}
@@ -0,0 +1,75 @@
+//! Folding the constant parts of an [`Expr`] while expanding it,
+//! shared by [`Polynomial::from_expr`] and [`LinearForm::from_expr`]
+
+use num::{BigRational, Zero};
+
+use crate::{
+	contexts::scalar::real::{Error, expr::Expr},
+	prelude::*,
+};
+
+use super::{polynomial::Polynomial, system::LinearForm};
+
+/// Powers bigger than this are rejected,
+/// since the exact number would take up too much memory
+pub(super) const MAX_EXPONENT: u32 = 1000;
+
+/// Exact powers estimated to take more bits than this are rejected,
+/// however small the exponent, e.g. `\left(10^{1000}\right)^{1000}`
+const MAX_POWER_BITS: u64 = 1 << 16;
+
+/// `base^power`, estimating the size of the result before computing it
+pub(super) fn checked_pow(base: &BigRational, power: i32) -> Result<BigRational, Error> {
+	if base.is_zero() && power < 0 {
+		return Err(Error::DivideByZero);
+	}
+	let bits = base.numer().bits().max(base.denom().bits());
+	if bits.saturating_mul(power.unsigned_abs().into()) > MAX_POWER_BITS {
+		return Err(Error::PowerTooLarge);
+	}
+	Ok(base.pow(power))
+}
+
+/// What an [`Expr`] is expanded into to solve it
+pub(super) trait Expanded: Sized {
+	/// For anything else, like dividing by a variable
+	const NOT_EXPANDED: Error;
+
+	fn as_constant(&self) -> Option<BigRational>;
+
+	/// `constant`, in the same variables as `self`
+	fn constant_like(&self, constant: BigRational) -> Self;
+
+	fn scale(&self, by: &BigRational) -> Self;
+
+	/// Only called if `self` isn't constant
+	fn pow(&self, power: u32) -> Result<Self, Error>;
+}
+
+/// Only dividing by a constant can be expanded
+pub(super) fn expand_div<E: Expanded>(numerator: E, denominator: E) -> Result<E, Error> {
+	let denominator = denominator.as_constant().ok_or(E::NOT_EXPANDED)?;
+	if denominator.is_zero() {
+		return Err(Error::DivideByZero);
+	}
+	Ok(numerator.scale(&denominator.recip()))
+}
+
+/// Only small integer powers can be expanded,
+/// and only non-negative ones unless `base` is constant
+pub(super) fn expand_exp<E: Expanded>(base: E, exponent: E) -> Result<E, Error> {
+	// roots aren't rational in general
+	let exponent = exponent
+		.as_constant()
+		.filter(|exponent| exponent.is_integer())
+		.ok_or(E::NOT_EXPANDED)?;
+	let power = i32::try_from(exponent.to_integer())
+		.ok()
+		.filter(|power| power.unsigned_abs() <= MAX_EXPONENT)
+		.ok_or(Error::PowerTooLarge)?;
+	match (base.as_constant(), u32::try_from(power)) {
+		(Some(constant), _) => Ok(base.constant_like(checked_pow(&constant, power)?)),
+		(None, Ok(power)) => base.pow(power),
+		(None, Err(_)) => Err(E::NOT_EXPANDED),
+	}
+}
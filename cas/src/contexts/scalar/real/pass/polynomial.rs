@@ -0,0 +1,754 @@
+//! Polynomials in [`ContextOneVarEq::solve_for`],
+//! solved exactly up to quadratics and numerically past that
+
+use latex_parser::Ident;
+use num::{BigInt, BigRational, BigUint, Integer, One, Signed, ToPrimitive, Zero, integer::Roots};
+
+use crate::{
+	contexts::scalar::real::{
+		ContextOneVarEq, Error,
+		expr::{ConstantNum, Equation, Expr, Ops},
+	},
+	prelude::*,
+};
+
+use super::{
+	eqs::{Move, Solution, Solved, Step, solve_linear},
+	expand::{Expanded, expand_div, expand_exp},
+	simplify::simplify,
+};
+
+/// Polynomials of a higher degree than this are rejected,
+/// since solving them would take too long
+const MAX_DEGREE: usize = 64;
+
+/// Numeric roots are refined until they're within this of the exact root
+const PRECISION: f64 = 1e-12;
+
+/// The coefficient of each power of `solve_for` from the constant up,
+/// without trailing zeros so the zero polynomial is empty
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial(Vec<BigRational>);
+
+impl Polynomial {
+	fn new(mut coefficients: Vec<BigRational>) -> Self {
+		while coefficients.last().is_some_and(Zero::is_zero) {
+			coefficients.pop();
+		}
+		Polynomial(coefficients)
+	}
+
+	fn constant(constant: BigRational) -> Self {
+		Polynomial::new(vec![constant])
+	}
+
+	/// `solve_for - root`
+	fn linear_factor(root: BigRational) -> Self {
+		Polynomial::new(vec![-root, BigRational::one()])
+	}
+
+	pub fn coefficients(&self) -> &[BigRational] {
+		&self.0
+	}
+
+	pub fn coefficient(&self, power: usize) -> BigRational {
+		self.0.get(power).cloned().unwrap_or_else(BigRational::zero)
+	}
+
+	/// `None` for the zero polynomial
+	pub fn degree(&self) -> Option<usize> {
+		self.0.len().checked_sub(1)
+	}
+
+	fn as_constant(&self) -> Option<BigRational> {
+		(self.0.len() <= 1).then(|| self.coefficient(0))
+	}
+
+	fn leading(&self) -> BigRational {
+		self.0.last().cloned().unwrap_or_else(BigRational::zero)
+	}
+
+	fn add(&self, other: &Polynomial) -> Polynomial {
+		let len = self.0.len().max(other.0.len());
+		Polynomial::new(
+			(0..len)
+				.map(|power| self.coefficient(power) + other.coefficient(power))
+				.collect(),
+		)
+	}
+
+	fn sub(&self, other: &Polynomial) -> Polynomial {
+		self.add(&other.scale(&-BigRational::one()))
+	}
+
+	fn scale(&self, by: &BigRational) -> Polynomial {
+		Polynomial::new(self.0.iter().map(|coefficient| coefficient * by).collect())
+	}
+
+	fn mul(&self, other: &Polynomial) -> Result<Polynomial, Error> {
+		if self.0.is_empty() || other.0.is_empty() {
+			return Ok(Polynomial(Vec::new()));
+		}
+		if self.0.len() + other.0.len() - 2 > MAX_DEGREE {
+			return Err(Error::PowerTooLarge);
+		}
+		let mut product = vec![BigRational::zero(); self.0.len() + other.0.len() - 1];
+		for (i, a) in self.0.iter().enumerate() {
+			for (j, b) in other.0.iter().enumerate() {
+				product[i + j] += a * b;
+			}
+		}
+		Ok(Polynomial::new(product))
+	}
+
+	fn eval(&self, x: &BigRational) -> BigRational {
+		self
+			.0
+			.iter()
+			.rev()
+			.fold(BigRational::zero(), |sum, coefficient| {
+				sum * x + coefficient
+			})
+	}
+
+	fn derivative(&self) -> Polynomial {
+		Polynomial::new(
+			self
+				.0
+				.iter()
+				.enumerate()
+				.skip(1)
+				.map(|(power, coefficient)| coefficient * BigRational::from_integer(power.into()))
+				.collect(),
+		)
+	}
+
+	/// Long division, `divisor` can't be zero
+	fn div_rem(&self, divisor: &Polynomial) -> (Polynomial, Polynomial) {
+		let divisor_degree = divisor.degree().expect("divisor to not be zero");
+		let mut remainder = self.0.clone();
+		let mut quotient = vec![BigRational::zero(); self.0.len().saturating_sub(divisor_degree)];
+		for power in (0..quotient.len()).rev() {
+			let factor = &remainder[power + divisor_degree] / divisor.leading();
+			for (i, coefficient) in divisor.0.iter().enumerate() {
+				remainder[power + i] -= &factor * coefficient;
+			}
+			quotient[power] = factor;
+		}
+		remainder.truncate(divisor_degree);
+		(Polynomial::new(quotient), Polynomial::new(remainder))
+	}
+
+	/// Without repeated roots, which [`sturm_sequence`] needs to isolate them
+	fn square_free(&self) -> Polynomial {
+		let mut a = self.clone();
+		let mut b = self.derivative();
+		while b.degree().is_some() {
+			let (_, remainder) = a.div_rem(&b);
+			(a, b) = (b, remainder);
+		}
+		self.div_rem(&a).0
+	}
+
+	/// Expands out brackets,
+	/// erroring on anything that isn't a polynomial in `solve_for` or isn't rational like τ
+	pub fn from_expr(solve_for: &Ident, expr: &Expr<Ident>) -> Result<Self, Error> {
+		let polynomial = |expr| Polynomial::from_expr(solve_for, expr);
+		match expr {
+			Expr::Constant(ConstantNum::Rational(rational)) => Ok(Polynomial::constant(rational.clone())),
+			Expr::Constant(ConstantNum::Tau) => Err(Error::IrrationalConstant),
+			Expr::Ident(ident) if ident == solve_for => {
+				Ok(Polynomial::linear_factor(BigRational::zero()))
+			}
+			Expr::Ident(ident) => Err(Error::UndefinedIdent(ident.clone())),
+			Expr::Ops(Ops::Add { exprs }) => {
+				exprs.iter().try_fold(Polynomial(Vec::new()), |sum, expr| {
+					Ok(sum.add(&polynomial(expr)?))
+				})
+			}
+			Expr::Ops(Ops::Mul { exprs }) => exprs
+				.iter()
+				.try_fold(Polynomial::constant(BigRational::one()), |product, expr| {
+					product.mul(&polynomial(expr)?)
+				}),
+			Expr::Ops(Ops::Div {
+				numerator,
+				denominator,
+			}) => expand_div(polynomial(numerator)?, polynomial(denominator)?),
+			Expr::Ops(Ops::Exp { base, exponent }) => {
+				expand_exp(polynomial(base)?, polynomial(exponent)?)
+			}
+		}
+	}
+
+	/// Simplified, see [`simplify`]
+	pub fn into_expr(&self, solve_for: &Ident) -> Expr<Ident> {
+		let terms = self.0.iter().enumerate().map(|(power, coefficient)| {
+			Ops::Mul {
+				exprs: vec![
+					ConstantNum::Rational(coefficient.clone()).into(),
+					Ops::Exp {
+						base: Box::new(solve_for.clone().into()),
+						exponent: Box::new(ConstantNum::integer(power).into()),
+					}
+					.into(),
+				],
+			}
+			.into()
+		});
+		simplify(
+			Ops::Add {
+				exprs: terms.collect(),
+			}
+			.into(),
+		)
+	}
+}
+
+impl Expanded for Polynomial {
+	const NOT_EXPANDED: Error = Error::NotPolynomial;
+
+	fn as_constant(&self) -> Option<BigRational> {
+		Polynomial::as_constant(self)
+	}
+
+	fn constant_like(&self, constant: BigRational) -> Self {
+		Polynomial::constant(constant)
+	}
+
+	fn scale(&self, by: &BigRational) -> Self {
+		Polynomial::scale(self, by)
+	}
+
+	fn pow(&self, power: u32) -> Result<Self, Error> {
+		(0..power).try_fold(Polynomial::constant(BigRational::one()), |product, _| {
+			product.mul(self)
+		})
+	}
+}
+
+/// A real root of a polynomial
+#[derive(Debug, Clone, PartialEq)]
+pub enum Root {
+	Exact(BigRational),
+	/// `rational + coefficient * sqrt(radicand)` from the quadratic formula,
+	/// with `radicand` not a perfect square
+	Surd {
+		rational: BigRational,
+		coefficient: BigRational,
+		radicand: BigUint,
+	},
+	/// Isolated numerically, when there's no exact form to find
+	Approx(f64),
+}
+
+impl Root {
+	pub fn to_f64(&self) -> f64 {
+		let to_f64 = |rational: &BigRational| rational.to_f64().unwrap_or(f64::NAN);
+		match self {
+			Root::Exact(rational) => to_f64(rational),
+			Root::Surd {
+				rational,
+				coefficient,
+				radicand,
+			} => to_f64(rational) + to_f64(coefficient) * radicand.to_f64().unwrap_or(f64::NAN).sqrt(),
+			Root::Approx(approx) => *approx,
+		}
+	}
+}
+
+/// Moves everything to the left, factors out any rational roots,
+/// then finds the roots of each factor.
+///
+/// If the equation is already factored like `x(x - 1) = 0` the factors are kept,
+/// so solving any step's equation gives the rest of the steps
+pub(super) fn solve_polynomial(
+	context: &ContextOneVarEq<Ident>,
+	eq: &Equation<Ident>,
+	lhs: Polynomial,
+	rhs: Polynomial,
+) -> Result<Solved, Error> {
+	let solve_for = &context.solve_for;
+	let mut steps = Vec::new();
+
+	let mut factors = if rhs.degree().is_none() && matches!(eq.lhs, Expr::Ops(Ops::Mul { .. })) {
+		factors_of(solve_for, &eq.lhs)?
+	} else {
+		let polynomial = lhs.sub(&rhs);
+		let moved = Equation {
+			lhs: polynomial.into_expr(solve_for),
+			rhs: ConstantNum::integer(0).into(),
+		};
+		if moved != *eq {
+			let r#move = match rhs.degree() {
+				None => Move::Collect,
+				Some(_) => Move::MoveToLeft,
+			};
+			steps.push(Step {
+				r#move,
+				equation: moved.clone(),
+			});
+		}
+		if polynomial.degree() <= Some(1) {
+			let solved = solve_linear(context, &moved)?;
+			steps.extend(solved.steps);
+			return Ok(Solved {
+				steps,
+				solution: solved.solution,
+			});
+		}
+		factors_of(solve_for, &moved.lhs)?
+	};
+
+	let mut i = 0;
+	while i < factors.len() {
+		if factors[i].degree() >= Some(3)
+			&& let Some(root) = rational_root(&factors[i])
+		{
+			let linear = Polynomial::linear_factor(root.clone());
+			let (quotient, _) = factors[i].div_rem(&linear);
+			factors.splice(i..=i, [linear, quotient]);
+			steps.push(Step {
+				r#move: Move::FactorOut {
+					root,
+					variable: solve_for.clone(),
+				},
+				equation: Equation {
+					lhs: Ops::Mul {
+						// flattened, as a factor like `2x^2` is itself a product
+						exprs: factors
+							.iter()
+							.flat_map(|factor| match factor.into_expr(solve_for) {
+								Expr::Ops(Ops::Mul { exprs }) => exprs,
+								factor => vec![factor],
+							})
+							.collect(),
+					}
+					.into(),
+					rhs: ConstantNum::integer(0).into(),
+				},
+			});
+		}
+		i += 1;
+	}
+
+	// finding roots doesn't change the equation
+	let factored = steps
+		.last()
+		.map(|step| step.equation.clone())
+		.unwrap_or_else(|| eq.clone());
+	let mut roots = Vec::new();
+	for factor in &factors {
+		let r#move = match factor.degree() {
+			None => {
+				return Ok(Solved {
+					steps,
+					solution: Solution::Infinite,
+				});
+			}
+			Some(0) => continue,
+			Some(1) => {
+				roots.push(Root::Exact(-factor.coefficient(0) / factor.coefficient(1)));
+				continue;
+			}
+			Some(2) => {
+				let (discriminant, quadratic) = quadratic_roots(factor);
+				roots.extend(quadratic);
+				Move::QuadraticFormula { discriminant }
+			}
+			Some(_) => {
+				roots.extend(approximate_roots(factor).into_iter().map(Root::Approx));
+				Move::ApproximateRoots
+			}
+		};
+		steps.push(Step {
+			r#move,
+			equation: factored.clone(),
+		});
+	}
+	roots.sort_by(|a, b| a.to_f64().total_cmp(&b.to_f64()));
+	roots.dedup();
+
+	let solution = match roots.is_empty() {
+		true => Solution::NoRealRoots,
+		false => Solution::Roots(roots),
+	};
+	Ok(Solved { steps, solution })
+}
+
+/// Each factor of a product, or just `expr`
+fn factors_of(solve_for: &Ident, expr: &Expr<Ident>) -> Result<Vec<Polynomial>, Error> {
+	match expr {
+		Expr::Ops(Ops::Mul { exprs }) => exprs
+			.iter()
+			.map(|expr| Polynomial::from_expr(solve_for, expr))
+			.collect(),
+		expr => Ok(vec![Polynomial::from_expr(solve_for, expr)?]),
+	}
+}
+
+/// The discriminant and roots by the quadratic formula,
+/// simplifying the square root of the discriminant
+fn quadratic_roots(quadratic: &Polynomial) -> (BigRational, Vec<Root>) {
+	let [c, b, a] = [0, 1, 2].map(|power| quadratic.coefficient(power));
+	let discriminant = &b * &b - BigRational::from_integer(4.into()) * &a * &c;
+	let two_a = BigRational::from_integer(2.into()) * &a;
+	let vertex = -&b / &two_a;
+	if discriminant.is_negative() {
+		return (discriminant, Vec::new());
+	}
+	if discriminant.is_zero() {
+		return (discriminant, vec![Root::Exact(vertex)]);
+	}
+
+	// sqrt(n/d) = sqrt(nd)/d
+	let denominator = discriminant.denom().magnitude().clone();
+	let (square, radicand) = split_square(discriminant.numer().magnitude() * &denominator);
+	let offset = BigRational::new(square.into(), BigInt::from(denominator)) / &two_a;
+	let roots = [-BigRational::one(), BigRational::one()]
+		.into_iter()
+		.map(|sign| match radicand.is_one() {
+			true => Root::Exact(&vertex + &offset * sign),
+			false => Root::Surd {
+				rational: vertex.clone(),
+				coefficient: &offset * sign,
+				radicand: radicand.clone(),
+			},
+		})
+		.collect();
+	(discriminant, roots)
+}
+
+/// `n` as `square^2 * radicand`, taking out small squares and checking if the rest is one
+fn split_square(mut n: BigUint) -> (BigUint, BigUint) {
+	let mut square = BigUint::one();
+	for factor in 2u32..1000 {
+		let factor_squared = BigUint::from(factor * factor);
+		while (&n % &factor_squared).is_zero() {
+			n /= &factor_squared;
+			square *= factor;
+		}
+	}
+	let root = n.sqrt();
+	if &root * &root == n {
+		return (square * root, BigUint::one());
+	}
+	(square, n)
+}
+
+/// By the rational root theorem,
+/// skipped if the coefficients have too many divisors to try
+fn rational_root(polynomial: &Polynomial) -> Option<BigRational> {
+	/// Candidates tried at most
+	const MAX_CANDIDATES: usize = 10_000;
+	if polynomial.coefficient(0).is_zero() {
+		return Some(BigRational::zero());
+	}
+	// with integer coefficients
+	let lcm = polynomial
+		.coefficients()
+		.iter()
+		.fold(BigInt::one(), |lcm, coefficient| {
+			lcm.lcm(coefficient.denom())
+		});
+	let integer = |power| (polynomial.coefficient(power) * &lcm).to_integer();
+	let divisors = |n: BigInt| -> Option<Vec<u64>> {
+		let n = n.magnitude().to_u64().filter(|n| *n <= 1_000_000_000_000)?;
+		let mut divisors = Vec::new();
+		for divisor in (1..).take_while(|divisor| divisor * divisor <= n) {
+			if n % divisor == 0 {
+				divisors.push(divisor);
+				if divisor * divisor != n {
+					divisors.push(n / divisor);
+				}
+			}
+		}
+		divisors.sort();
+		Some(divisors)
+	};
+	let numerators = divisors(integer(0))?;
+	let denominators = divisors(integer(polynomial.degree()?))?;
+	if numerators.len() * denominators.len() > MAX_CANDIDATES {
+		return None;
+	}
+	denominators
+		.iter()
+		.flat_map(|denominator| {
+			numerators.iter().flat_map(move |numerator| {
+				let candidate = BigRational::new((*numerator).into(), (*denominator).into());
+				[candidate.clone(), -candidate]
+			})
+		})
+		.find(|candidate| polynomial.eval(candidate).is_zero())
+}
+
+/// `p`, `p'`, then the negated remainder of the two before,
+/// for counting real roots with Sturm's theorem
+fn sturm_sequence(polynomial: &Polynomial) -> Vec<Polynomial> {
+	let mut sequence = vec![polynomial.clone(), polynomial.derivative()];
+	while let [.., a, b] = &sequence[..]
+		&& b.degree().is_some()
+	{
+		let (_, remainder) = a.div_rem(b);
+		sequence.push(remainder.scale(&-BigRational::one()));
+	}
+	sequence.pop();
+	sequence
+}
+
+/// Every real root, isolated in an interval with Sturm's theorem then bisected
+fn approximate_roots(polynomial: &Polynomial) -> Vec<f64> {
+	let polynomial = polynomial.square_free();
+	let sequence = sturm_sequence(&polynomial);
+	let sign_changes = |x: &BigRational| {
+		let signs: Vec<bool> = sequence
+			.iter()
+			.map(|polynomial| polynomial.eval(x))
+			.filter(|value| !value.is_zero())
+			.map(|value| value.is_positive())
+			.collect();
+		signs.windows(2).filter(|pair| pair[0] != pair[1]).count()
+	};
+	// within (-bound, bound) by Cauchy's bound
+	let bound = BigRational::one()
+		+ polynomial
+			.coefficients()
+			.iter()
+			.map(|coefficient| (coefficient / polynomial.leading()).abs())
+			.max()
+			.unwrap_or_else(BigRational::zero);
+
+	let mut roots = Vec::new();
+	let mut intervals = vec![(-bound.clone(), bound)];
+	while let Some((low, high)) = intervals.pop() {
+		match sign_changes(&low) - sign_changes(&high) {
+			0 => {}
+			1 => roots.push(bisect(&polynomial, low, high)),
+			_ => {
+				let middle = split_point(&polynomial, &low, &high);
+				intervals.push((low, middle.clone()));
+				intervals.push((middle, high));
+			}
+		}
+	}
+	roots.sort_by(f64::total_cmp);
+	roots
+}
+
+/// Somewhere strictly between `low` and `high` which isn't a root,
+/// so Sturm's theorem can count the roots either side
+fn split_point(polynomial: &Polynomial, low: &BigRational, high: &BigRational) -> BigRational {
+	(2u32..)
+		.flat_map(|denominator| {
+			(1..denominator).map(move |numerator| BigRational::new(numerator.into(), denominator.into()))
+		})
+		.map(|fraction| low + (high - low) * fraction)
+		.find(|point| !polynomial.eval(point).is_zero())
+		.expect("a polynomial to have finitely many roots")
+}
+
+/// The only root between `low` and `high`, where `polynomial` changes sign as it's square free
+fn bisect(polynomial: &Polynomial, mut low: BigRational, mut high: BigRational) -> f64 {
+	let precision = BigRational::from_float(PRECISION).expect("precision to be finite");
+	let low_positive = polynomial.eval(&low).is_positive();
+	while &high - &low > precision {
+		let middle = (&low + &high) / BigRational::from_integer(2.into());
+		let value = polynomial.eval(&middle);
+		if value.is_zero() {
+			return middle.to_f64().unwrap_or(f64::NAN);
+		}
+		if value.is_positive() == low_positive {
+			low = middle;
+		} else {
+			high = middle;
+		}
+	}
+	((&low + &high) / BigRational::from_integer(2.into()))
+		.to_f64()
+		.unwrap_or(f64::NAN)
+}
+
+#[test]
+fn solves_polynomials() {
+	use super::eqs::solve;
+	use crate::contexts::scalar::real::parse_one_var;
+
+	let solve = |latex| {
+		let (context, eq) = parse_one_var(latex).unwrap();
+		solve(&context, &eq)
+	};
+	let roots = |latex| match solve(latex).unwrap().solution {
+		Solution::Roots(roots) => roots,
+		solution => panic!("Expected roots for {}, found {:?}", latex, solution),
+	};
+	let exact = |numerator: i32, denominator: i32| {
+		Root::Exact(BigRational::new(numerator.into(), denominator.into()))
+	};
+	let surd = |rational: (i32, i32), coefficient: (i32, i32), radicand: u32| Root::Surd {
+		rational: BigRational::new(rational.0.into(), rational.1.into()),
+		coefficient: BigRational::new(coefficient.0.into(), coefficient.1.into()),
+		radicand: radicand.into(),
+	};
+
+	let solved = solve(r"x^{2} = 4").unwrap();
+	let moves: Vec<_> = solved
+		.steps
+		.iter()
+		.map(|step| step.r#move.to_string())
+		.collect();
+	assert_eq!(
+		moves,
+		vec![
+			"subtract the right side from both sides",
+			"use the quadratic formula, the discriminant is 16"
+		]
+	);
+	assert_eq!(
+		solved.solution,
+		Solution::Roots(vec![exact(-2, 1), exact(2, 1)])
+	);
+
+	assert_eq!(
+		roots(r"x^{2} + x - 1 = 0"),
+		vec![surd((-1, 2), (-1, 2), 5), surd((-1, 2), (1, 2), 5)]
+	);
+	assert_eq!(
+		roots(r"x^{2} = 8"),
+		vec![surd((0, 1), (-2, 1), 2), surd((0, 1), (2, 1), 2)]
+	);
+	assert_eq!(roots(r"x^{2} - 2x + 1 = 0"), vec![exact(1, 1)]);
+	assert_eq!(roots(r"x^{2} = 4 - -5"), vec![exact(-3, 1), exact(3, 1)]);
+	assert_eq!(roots(r"2x^{2} = x"), vec![exact(0, 1), exact(1, 2)]);
+	let solved = solve(r"x^{2} + 1 = 0").unwrap();
+	assert_eq!(
+		solved.steps.last().unwrap().r#move.to_string(),
+		"use the quadratic formula, the discriminant -4 is negative so there are no real roots"
+	);
+	assert_eq!(solved.solution, Solution::NoRealRoots);
+	assert_eq!(
+		solve(r"x^{4} + 1 = 0").unwrap().solution,
+		Solution::NoRealRoots
+	);
+	// already factored
+	let solved = solve(r"x\left(x + 1\right) = 0").unwrap();
+	assert!(solved.steps.is_empty());
+	assert_eq!(
+		solved.solution,
+		Solution::Roots(vec![exact(-1, 1), exact(0, 1)])
+	);
+	// the squares cancel, leaving a linear equation
+	let solved = solve(r"x^{2} + x = x^{2} + 1").unwrap();
+	assert_eq!(solved.steps.len(), 2);
+	assert_eq!(
+		solved.solution,
+		Solution::Value(BigRational::from_integer(1.into()))
+	);
+
+	let solved = solve(r"x^{3} - 6x^{2} + 11x - 6 = 0").unwrap();
+	let moves: Vec<_> = solved
+		.steps
+		.iter()
+		.map(|step| step.r#move.to_string())
+		.collect();
+	// `- 6x^{2}` lowers to `-1 \cdot 6x^{2}`, which is collected first
+	assert_eq!(
+		moves,
+		vec![
+			"expand and collect like terms",
+			"factor out (x - 1)",
+			"use the quadratic formula, the discriminant is 1"
+		]
+	);
+	assert_eq!(
+		solved.solution,
+		Solution::Roots(vec![exact(1, 1), exact(2, 1), exact(3, 1)])
+	);
+	assert_eq!(
+		roots(r"x^{4} + 3x^{3} = 0"),
+		vec![exact(-3, 1), exact(0, 1)]
+	);
+	// factoring `x` out of `2x^{3}` leaves a product
+	let solved = solve(r"\left(x + 1\right)\left(x^{3} + x^{3}\right) = 0").unwrap();
+	let Expr::Ops(Ops::Mul { exprs }) = &solved.steps[0].equation.lhs else {
+		panic!("Expected the factors to be a product");
+	};
+	assert_eq!(exprs.len(), 4);
+	assert!(
+		!exprs
+			.iter()
+			.any(|factor| matches!(factor, Expr::Ops(Ops::Mul { .. })))
+	);
+
+	let approx = |latex| {
+		roots(latex)
+			.into_iter()
+			.map(|root| match root {
+				Root::Approx(approx) => approx,
+				root => panic!("Expected an approximate root, found {:?}", root),
+			})
+			.collect::<Vec<_>>()
+	};
+	let close = |found: Vec<f64>, expected: &[f64]| {
+		assert_eq!(found.len(), expected.len(), "{:?}", found);
+		for (found, expected) in found.iter().zip(expected) {
+			assert!(
+				(found - expected).abs() < 1e-9,
+				"{} isn't {}",
+				found,
+				expected
+			);
+		}
+	};
+	close(approx(r"x^{3} = 2"), &[2f64.cbrt()]);
+	close(approx(r"x^{5} - x - 1 = 0"), &[1.1673039782614187]);
+	let (root_2, root_3) = (2f64.sqrt(), 3f64.sqrt());
+	close(
+		approx(r"x^{4} - 5x^{2} + 6 = 0"),
+		&[-root_3, -root_2, root_2, root_3],
+	);
+	// a repeated root doesn't change sign
+	close(approx(r"\left(x^{3} - 2\right)^{2} = 0"), &[2f64.cbrt()]);
+
+	assert_eq!(
+		solve(r"x^{5} - x - 1 = 0")
+			.unwrap()
+			.steps
+			.last()
+			.unwrap()
+			.r#move,
+		Move::ApproximateRoots
+	);
+
+	assert!(matches!(
+		solve(r"\frac{1}{x} = 2"),
+		Err(Error::NotPolynomial)
+	));
+	assert!(matches!(solve(r"x^{100} = 2"), Err(Error::PowerTooLarge)));
+}
+
+#[test]
+fn polynomial_steps_are_lines() {
+	use crate::contexts::scalar::real::RealScalarStorage;
+
+	let mut storage =
+		RealScalarStorage::from_latext_eq(r"x^{3} - 6x^{2} + 11x = 6".to_owned()).unwrap();
+	let Solution::Roots(roots) = storage.solve().unwrap() else {
+		panic!("Expected roots");
+	};
+	assert_eq!(roots.len(), 3);
+	// the quadratic formula only finds roots, so isn't a line
+	let descriptions: Vec<_> = storage
+		.storage()
+		.derivation()
+		.iter()
+		.skip(1)
+		.map(|line| line.derived().unwrap().description.clone())
+		.collect();
+	assert_eq!(
+		descriptions,
+		vec![
+			"subtract the right side from both sides",
+			"factor out (x - 1)"
+		]
+	);
+}
@@ -0,0 +1,390 @@
+//! Canonicalizes an [`Expr`], so equivalent answers like `\frac{x}{2} + 1`
+//! and `1 + 0.5x` simplify to the same tree
+
+use std::cmp::Ordering;
+
+use bevy_ecs::{
+	entity::Entity,
+	system::{In, Query},
+};
+use latex_parser::Ident;
+use num::{BigRational, One, Signed, Zero};
+
+use crate::{
+	contexts::scalar::real::{
+		Error, Line,
+		expr::{ConstantNum, Equation, Expr, Ops},
+	},
+	prelude::*,
+	storage::Rewrite,
+};
+
+use super::expand::{MAX_EXPONENT, checked_pow};
+
+/// - nested [`Ops::Add`] and [`Ops::Mul`] are flattened
+/// - rational constants are folded, and come first in a product and last in a sum
+/// - like terms like `2x + 3x` are collected into `5x`
+/// - powers of the same base like `x \cdot x^2` are collected into `x^3`
+/// - division is multiplying by a power of `-1`, unless dividing by zero
+/// - powers that cancel are dropped, so `\frac{x}{x}` is `1`
+///   without the restriction that `x` isn't zero
+/// - everything else is ordered by [`order`]
+pub fn simplify(expr: Expr<Ident>) -> Expr<Ident> {
+	match expr {
+		Expr::Constant(_) | Expr::Ident(_) => expr,
+		Expr::Ops(Ops::Add { exprs }) => simplify_add(exprs.into_iter().map(simplify)),
+		Expr::Ops(Ops::Mul { exprs }) => simplify_mul(exprs.into_iter().map(simplify)),
+		Expr::Ops(Ops::Div {
+			numerator,
+			denominator,
+		}) => {
+			let numerator = simplify(*numerator);
+			let denominator = simplify(*denominator);
+			if as_rational(&denominator).is_some_and(Zero::is_zero) {
+				return Ops::Div {
+					numerator: Box::new(numerator),
+					denominator: Box::new(denominator),
+				}
+				.into();
+			}
+			simplify_mul([numerator, simplify_exp(denominator, rational(-1))])
+		}
+		Expr::Ops(Ops::Exp { base, exponent }) => simplify_exp(simplify(*base), simplify(*exponent)),
+	}
+}
+
+fn rational(integer: i32) -> Expr<Ident> {
+	ConstantNum::integer(integer).into()
+}
+
+fn as_rational(expr: &Expr<Ident>) -> Option<&BigRational> {
+	match expr {
+		Expr::Constant(ConstantNum::Rational(rational)) => Some(rational),
+		_ => None,
+	}
+}
+
+/// `terms` are already simplified
+fn simplify_add(terms: impl IntoIterator<Item = Expr<Ident>>) -> Expr<Ident> {
+	// like terms share their factors, only their coefficient differs
+	let mut collected: Vec<(Vec<Expr<Ident>>, BigRational)> = Vec::new();
+	for term in terms {
+		let terms = match term {
+			Expr::Ops(Ops::Add { exprs }) => exprs,
+			term => vec![term],
+		};
+		for term in terms {
+			let (coefficient, factors) = split_coefficient(term);
+			match collected.iter_mut().find(|(like, _)| *like == factors) {
+				Some((_, sum)) => *sum += coefficient,
+				None => collected.push((factors, coefficient)),
+			}
+		}
+	}
+	collected.retain(|(_, coefficient)| !coefficient.is_zero());
+	collected.sort_by(|(a, _), (b, _)| {
+		// the constant term last
+		a.is_empty()
+			.cmp(&b.is_empty())
+			.then_with(|| order_all(a, b))
+	});
+
+	let mut exprs: Vec<_> = collected
+		.into_iter()
+		.map(|(factors, coefficient)| product(coefficient, factors))
+		.collect();
+	match exprs.len() {
+		0 => rational(0),
+		1 => exprs.remove(0),
+		_ => Ops::Add { exprs }.into(),
+	}
+}
+
+/// The inverse of [`product`], for an already simplified `term`
+fn split_coefficient(term: Expr<Ident>) -> (BigRational, Vec<Expr<Ident>>) {
+	match term {
+		Expr::Constant(ConstantNum::Rational(rational)) => (rational, Vec::new()),
+		Expr::Ops(Ops::Mul { mut exprs }) => match exprs.first().and_then(as_rational) {
+			Some(coefficient) => {
+				let coefficient = coefficient.clone();
+				exprs.remove(0);
+				(coefficient, exprs)
+			}
+			None => (BigRational::one(), exprs),
+		},
+		term => (BigRational::one(), vec![term]),
+	}
+}
+
+/// `factors` are already simplified and ordered
+fn product(coefficient: BigRational, mut factors: Vec<Expr<Ident>>) -> Expr<Ident> {
+	if coefficient.is_zero() || factors.is_empty() {
+		return ConstantNum::Rational(coefficient).into();
+	}
+	if !coefficient.is_one() {
+		factors.insert(0, ConstantNum::Rational(coefficient).into());
+	}
+	match factors.len() {
+		1 => factors.remove(0),
+		_ => Ops::Mul { exprs: factors }.into(),
+	}
+}
+
+/// `factors` are already simplified.
+///
+/// A base whose powers sum to zero is dropped, like in `x \cdot x^{-1}`,
+/// which loses that the base can't be zero
+fn simplify_mul(factors: impl IntoIterator<Item = Expr<Ident>>) -> Expr<Ident> {
+	let mut coefficient = BigRational::one();
+	let mut powers: Vec<(Expr<Ident>, BigRational)> = Vec::new();
+	for factor in factors {
+		let factors = match factor {
+			Expr::Ops(Ops::Mul { exprs }) => exprs,
+			factor => vec![factor],
+		};
+		for factor in factors {
+			let (base, exponent) = match factor {
+				Expr::Constant(ConstantNum::Rational(rational)) => {
+					coefficient *= rational;
+					continue;
+				}
+				Expr::Ops(Ops::Exp { base, exponent }) => match *exponent {
+					Expr::Constant(ConstantNum::Rational(exponent)) => (*base, exponent),
+					exponent => (
+						Ops::Exp {
+							base,
+							exponent: Box::new(exponent),
+						}
+						.into(),
+						BigRational::one(),
+					),
+				},
+				factor => (factor, BigRational::one()),
+			};
+			match powers.iter_mut().find(|(like, _)| *like == base) {
+				Some((_, sum)) => *sum += exponent,
+				None => powers.push((base, exponent)),
+			}
+		}
+	}
+
+	let mut factors: Vec<_> = powers
+		.into_iter()
+		.filter(|(_, exponent)| !exponent.is_zero())
+		.map(|(base, exponent)| match exponent.is_one() {
+			true => base,
+			false => Ops::Exp {
+				base: Box::new(base),
+				exponent: Box::new(ConstantNum::Rational(exponent).into()),
+			}
+			.into(),
+		})
+		.collect();
+	factors.sort_by(order);
+	product(coefficient, factors)
+}
+
+/// `base` and `exponent` are already simplified
+fn simplify_exp(base: Expr<Ident>, exponent: Expr<Ident>) -> Expr<Ident> {
+	let Some(power) = as_rational(&exponent) else {
+		return Ops::Exp {
+			base: Box::new(base),
+			exponent: Box::new(exponent),
+		}
+		.into();
+	};
+	if power.is_zero() {
+		return rational(1);
+	}
+	if power.is_one() {
+		return base;
+	}
+	// only integer powers distribute in the reals, e.g. `(x^2)^{1/2}` is `|x|`
+	let integer = power
+		.is_integer()
+		.then(|| i32::try_from(power.to_integer()).ok())
+		.flatten()
+		.filter(|power| power.unsigned_abs() <= MAX_EXPONENT);
+	match (base, integer) {
+		(Expr::Constant(ConstantNum::Rational(base)), Some(integer)) => {
+			match checked_pow(&base, integer) {
+				Ok(power) => ConstantNum::Rational(power).into(),
+				// left for solving to report
+				Err(_) => Ops::Exp {
+					base: Box::new(ConstantNum::Rational(base).into()),
+					exponent: Box::new(exponent),
+				}
+				.into(),
+			}
+		}
+		(Expr::Ops(Ops::Exp { base, exponent }), Some(_)) if as_rational(&exponent).is_some() => {
+			let exponent = as_rational(&exponent).unwrap() * power;
+			simplify_exp(*base, ConstantNum::Rational(exponent).into())
+		}
+		(Expr::Ops(Ops::Mul { exprs }), Some(_)) => simplify_mul(
+			exprs
+				.into_iter()
+				.map(|factor| simplify_exp(factor, exponent.clone())),
+		),
+		(base, _) => Ops::Exp {
+			base: Box::new(base),
+			exponent: Box::new(exponent),
+		}
+		.into(),
+	}
+}
+
+/// A total order, so simplifying always orders terms and factors the same way.
+///
+/// Constants come first, then variables, then operations
+pub fn order(a: &Expr<Ident>, b: &Expr<Ident>) -> Ordering {
+	fn rank(expr: &Expr<Ident>) -> u8 {
+		match expr {
+			Expr::Constant(ConstantNum::Rational(_)) => 0,
+			Expr::Constant(ConstantNum::Tau) => 1,
+			Expr::Ident(_) => 2,
+			Expr::Ops(Ops::Exp { .. }) => 3,
+			Expr::Ops(Ops::Mul { .. }) => 4,
+			Expr::Ops(Ops::Add { .. }) => 5,
+			Expr::Ops(Ops::Div { .. }) => 6,
+		}
+	}
+	match (a, b) {
+		(Expr::Constant(ConstantNum::Rational(a)), Expr::Constant(ConstantNum::Rational(b))) => {
+			a.cmp(b)
+		}
+		(Expr::Ident(a), Expr::Ident(b)) => a.cmp(b),
+		(
+			Expr::Ops(Ops::Exp {
+				base: a_base,
+				exponent: a_exponent,
+			}),
+			Expr::Ops(Ops::Exp {
+				base: b_base,
+				exponent: b_exponent,
+			}),
+		) => order(a_base, b_base).then_with(|| order(a_exponent, b_exponent)),
+		(Expr::Ops(Ops::Mul { exprs: a }), Expr::Ops(Ops::Mul { exprs: b }))
+		| (Expr::Ops(Ops::Add { exprs: a }), Expr::Ops(Ops::Add { exprs: b })) => order_all(a, b),
+		(
+			Expr::Ops(Ops::Div {
+				numerator: a_numerator,
+				denominator: a_denominator,
+			}),
+			Expr::Ops(Ops::Div {
+				numerator: b_numerator,
+				denominator: b_denominator,
+			}),
+		) => order(a_numerator, b_numerator).then_with(|| order(a_denominator, b_denominator)),
+		(a, b) => rank(a).cmp(&rank(b)),
+	}
+}
+
+/// Lexicographically by [`order`]
+fn order_all(a: &[Expr<Ident>], b: &[Expr<Ident>]) -> Ordering {
+	a.iter()
+		.zip(b)
+		.map(|(a, b)| order(a, b))
+		.find(|ordering| ordering.is_ne())
+		.unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+/// Simplifies both sides as a pass,
+/// which rewrites nothing if they're already simplified
+pub fn simplify_step(
+	In(line): In<Entity>,
+	equations: Query<&Equation<Ident>>,
+) -> Result<Option<Rewrite<Line>>, Error> {
+	let eq = equations.get(line).expect("lines to be equations");
+	let simplified = Equation {
+		lhs: simplify(eq.lhs.clone()),
+		rhs: simplify(eq.rhs.clone()),
+	};
+	Ok((simplified != *eq).then(|| Rewrite {
+		line: Line::new(simplified),
+		description: "simplify".to_owned(),
+	}))
+}
+
+#[test]
+fn simplifies() {
+	use crate::contexts::scalar::real::parse_one_var;
+
+	let simplified = |latex: &str| simplify(parse_one_var(&format!("{} = 0", latex)).unwrap().1.lhs);
+	let x = || Expr::Ident(Ident::AlphabeticChar('x'));
+	let x_to = |power: i32| {
+		Expr::from(Ops::Exp {
+			base: Box::new(x()),
+			exponent: Box::new(rational(power)),
+		})
+	};
+	let mul = |exprs| Expr::Ops(Ops::Mul { exprs });
+
+	assert_eq!(simplified(r"2x + 3x - x"), mul(vec![rational(4), x()]));
+	assert_eq!(
+		simplified(r"x \cdot x \cdot 2"),
+		mul(vec![rational(2), x_to(2)])
+	);
+	assert_eq!(simplified(r"x - x"), rational(0));
+	assert_eq!(simplified(r"x^{2} x^{3}"), x_to(5));
+	assert_eq!(simplified(r"\frac{x^{3}}{x}"), x_to(2));
+	assert_eq!(simplified(r"\frac{2}{x}"), mul(vec![rational(2), x_to(-1)]));
+	// without `x \ne 0`
+	assert_eq!(simplified(r"x \cdot x^{-1}"), rational(1));
+	assert_eq!(simplified(r"x + 2^{3} + 1 - x"), rational(9));
+	// too big to fold, however small each exponent
+	let Expr::Ops(Ops::Mul { exprs }) =
+		simplified(r"x \left(\left(10^{1000}\right)^{1000}\right)^{1000}")
+	else {
+		panic!("Expected the power to be left as a factor");
+	};
+	assert!(matches!(
+		exprs[..],
+		[Expr::Ident(_), Expr::Ops(Ops::Exp { .. })]
+	));
+	assert_eq!(
+		simplified(r"\left(2x\right)^{2}"),
+		mul(vec![rational(4), x_to(2)])
+	);
+	assert_eq!(
+		simplified(r"\left(x + 1\right) + \left(2 + x\right)"),
+		Ops::Add {
+			exprs: vec![mul(vec![rational(2), x()]), rational(3)]
+		}
+		.into()
+	);
+
+	// equivalent answers are the same tree
+	for (a, b) in [
+		(r"\frac{x}{2} + 1", r"1 + 0.5x"),
+		(r"x \cdot 2 + 3x^{2}", r"2x + x x 3"),
+		(r"\frac{1}{x^{2}}", r"x^{-2}"),
+		(
+			r"\left(x + 1\right)\left(x + 2\right)",
+			r"\left(2 + x\right)\left(1 + x\right)",
+		),
+	] {
+		assert_eq!(simplified(a), simplified(b), "{} and {}", a, b);
+	}
+
+	// simplifying again changes nothing
+	for latex in [
+		r"2x + 3x^{2} - \frac{x}{\tau}",
+		r"\left(x + 1\right)^{2} \cdot 2x^{-1}",
+	] {
+		let once = simplified(latex);
+		assert_eq!(simplify(once.clone()), once, "{}", latex);
+	}
+
+	let (_context, eq) = parse_one_var(r"x x = 2 + 2").unwrap();
+	let mut storage = crate::storage::Storage::new(Line::new(eq));
+	let line = storage.run_pass(simplify_step).unwrap().unwrap();
+	assert_eq!(
+		line.get::<Equation<Ident>>().unwrap(),
+		&Equation {
+			lhs: x_to(2),
+			rhs: rational(4),
+		}
+	);
+	assert!(storage.run_pass(simplify_step).unwrap().is_none());
+}
@@ -0,0 +1,326 @@
+//! Solving several linear [`Equation`]s for every one of
+//! [`ContextLinearSystem::unknowns`] at once, by Gaussian elimination
+
+use latex_parser::Ident;
+use num::{BigRational, One, Zero};
+
+use crate::{
+	contexts::scalar::real::{
+		ContextLinearSystem, Error,
+		expr::{ConstantNum, Equation, Expr, Ops},
+	},
+	prelude::*,
+};
+
+use super::{
+	expand::{Expanded, expand_div, expand_exp},
+	simplify::simplify,
+};
+
+/// `coefficients[i] * unknowns[i] + ... + constant`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearForm {
+	pub coefficients: Vec<BigRational>,
+	pub constant: BigRational,
+}
+
+impl LinearForm {
+	fn constant(unknowns: usize, constant: BigRational) -> Self {
+		LinearForm {
+			coefficients: vec![BigRational::zero(); unknowns],
+			constant,
+		}
+	}
+
+	fn as_constant(&self) -> Option<&BigRational> {
+		self
+			.coefficients
+			.iter()
+			.all(Zero::is_zero)
+			.then_some(&self.constant)
+	}
+
+	fn add(mut self, other: &LinearForm) -> LinearForm {
+		for (coefficient, other) in self.coefficients.iter_mut().zip(&other.coefficients) {
+			*coefficient += other;
+		}
+		self.constant += &other.constant;
+		self
+	}
+
+	fn scale(mut self, by: &BigRational) -> LinearForm {
+		for coefficient in &mut self.coefficients {
+			*coefficient *= by;
+		}
+		self.constant *= by;
+		self
+	}
+
+	/// Only if either side is constant
+	fn mul(self, other: LinearForm) -> Result<LinearForm, Error> {
+		if let Some(constant) = self.as_constant() {
+			Ok(other.scale(constant))
+		} else if let Some(constant) = other.as_constant() {
+			Ok(self.scale(constant))
+		} else {
+			Err(Error::NotLinear)
+		}
+	}
+
+	/// Like [`Polynomial::from_expr`](super::polynomial::Polynomial::from_expr) in several unknowns,
+	/// erroring on products of them instead
+	pub fn from_expr(unknowns: &[Ident], expr: &Expr<Ident>) -> Result<Self, Error> {
+		let form = |expr| LinearForm::from_expr(unknowns, expr);
+		let constant = |constant| LinearForm::constant(unknowns.len(), constant);
+		match expr {
+			Expr::Constant(ConstantNum::Rational(rational)) => Ok(constant(rational.clone())),
+			Expr::Constant(ConstantNum::Tau) => Err(Error::IrrationalConstant),
+			Expr::Ident(ident) => {
+				let index = unknowns
+					.iter()
+					.position(|unknown| unknown == ident)
+					.ok_or_else(|| Error::UndefinedIdent(ident.clone()))?;
+				let mut variable = constant(BigRational::zero());
+				variable.coefficients[index] = BigRational::one();
+				Ok(variable)
+			}
+			Expr::Ops(Ops::Add { exprs }) => exprs
+				.iter()
+				.try_fold(constant(BigRational::zero()), |sum, expr| {
+					Ok(sum.add(&form(expr)?))
+				}),
+			Expr::Ops(Ops::Mul { exprs }) => exprs
+				.iter()
+				.try_fold(constant(BigRational::one()), |product, expr| {
+					product.mul(form(expr)?)
+				}),
+			Expr::Ops(Ops::Div {
+				numerator,
+				denominator,
+			}) => expand_div(form(numerator)?, form(denominator)?),
+			Expr::Ops(Ops::Exp { base, exponent }) => expand_exp(form(base)?, form(exponent)?),
+		}
+	}
+
+	/// Simplified, see [`simplify`]
+	pub fn into_expr(&self, unknowns: &[Ident]) -> Expr<Ident> {
+		let terms = self
+			.coefficients
+			.iter()
+			.zip(unknowns)
+			.map(|(coefficient, unknown)| {
+				Ops::Mul {
+					exprs: vec![
+						ConstantNum::Rational(coefficient.clone()).into(),
+						unknown.clone().into(),
+					],
+				}
+				.into()
+			})
+			.chain([ConstantNum::Rational(self.constant.clone()).into()]);
+		simplify(
+			Ops::Add {
+				exprs: terms.collect(),
+			}
+			.into(),
+		)
+	}
+}
+
+impl Expanded for LinearForm {
+	const NOT_EXPANDED: Error = Error::NotLinear;
+
+	fn as_constant(&self) -> Option<BigRational> {
+		LinearForm::as_constant(self).cloned()
+	}
+
+	fn constant_like(&self, constant: BigRational) -> Self {
+		LinearForm::constant(self.coefficients.len(), constant)
+	}
+
+	fn scale(&self, by: &BigRational) -> Self {
+		LinearForm::scale(self.clone(), by)
+	}
+
+	fn pow(&self, power: u32) -> Result<Self, Error> {
+		match power {
+			0 => Ok(self.constant_like(BigRational::one())),
+			1 => Ok(self.clone()),
+			_ => Err(Error::NotLinear),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SystemSolution {
+	/// The value of every unknown
+	Unique(Vec<(Ident, BigRational)>),
+	/// Some equations contradict each other
+	None,
+	/// Infinitely many solutions, one for every value of the `free` unknowns
+	Parametric {
+		free: Vec<Ident>,
+		/// Every other unknown in terms of the `free` ones
+		dependent: Vec<(Ident, LinearForm)>,
+	},
+}
+
+/// Gaussian elimination over the rationals,
+/// so the solution is exact
+pub fn solve_system(
+	context: &ContextLinearSystem<Ident>,
+	equations: &[Equation<Ident>],
+) -> Result<SystemSolution, Error> {
+	let unknowns = context.unknowns();
+	// `coefficients | constant`, with the unknowns on the left and the constant on the right
+	let mut rows = equations
+		.iter()
+		.enumerate()
+		.map(|(index, eq)| {
+			let row = || {
+				let lhs = LinearForm::from_expr(unknowns, &eq.lhs)?;
+				let rhs = LinearForm::from_expr(unknowns, &eq.rhs)?;
+				let mut row = lhs.add(&rhs.scale(&-BigRational::one()));
+				row.constant = -row.constant;
+				Ok(row)
+			};
+			row().map_err(|err| Error::in_equation(index, err))
+		})
+		.collect::<Result<Vec<_>, Error>>()?;
+
+	let pivots = reduce(&mut rows, unknowns.len());
+
+	// a row of `0 = c` left over
+	if rows[pivots.len()..]
+		.iter()
+		.any(|row| !row.constant.is_zero())
+	{
+		return Ok(SystemSolution::None);
+	}
+
+	if pivots.len() == unknowns.len() {
+		return Ok(SystemSolution::Unique(
+			unknowns
+				.iter()
+				.cloned()
+				.zip(rows.into_iter().map(|row| row.constant))
+				.collect(),
+		));
+	}
+
+	let free = (0..unknowns.len())
+		.filter(|column| !pivots.contains(column))
+		.collect::<Vec<_>>();
+	let dependent = pivots
+		.iter()
+		.zip(rows)
+		.map(|(&pivot, row)| {
+			// `unknown + sum of free terms = constant`
+			let mut value = LinearForm::constant(unknowns.len(), row.constant);
+			for &column in &free {
+				value.coefficients[column] = -&row.coefficients[column];
+			}
+			(unknowns[pivot].clone(), value)
+		})
+		.collect();
+	Ok(SystemSolution::Parametric {
+		free: free
+			.into_iter()
+			.map(|column| unknowns[column].clone())
+			.collect(),
+		dependent,
+	})
+}
+
+/// Into reduced row echelon form,
+/// returning the column of each pivot which leads the first rows
+fn reduce(rows: &mut [LinearForm], columns: usize) -> Vec<usize> {
+	let mut pivots = Vec::new();
+	for column in 0..columns {
+		let row = pivots.len();
+		let Some(pivot) = (row..rows.len()).find(|&pivot| !rows[pivot].coefficients[column].is_zero())
+		else {
+			continue;
+		};
+		rows.swap(row, pivot);
+
+		let leading = rows[row].coefficients[column].recip();
+		rows[row] = rows[row].clone().scale(&leading);
+		let pivot_row = rows[row].clone();
+		for (other, other_row) in rows.iter_mut().enumerate() {
+			let factor = other_row.coefficients[column].clone();
+			if other != row && !factor.is_zero() {
+				*other_row = other_row.clone().add(&pivot_row.clone().scale(&-factor));
+			}
+		}
+
+		pivots.push(column);
+		if pivots.len() == rows.len() {
+			break;
+		}
+	}
+	pivots
+}
+
+#[test]
+fn solves_systems() {
+	use crate::contexts::scalar::real::RealScalarSystem;
+
+	let solve = |equations: &[&str]| {
+		RealScalarSystem::from_latex_eqs(equations.iter().map(|latex| latex.to_string()))
+			.and_then(|system| system.solve())
+	};
+	let ident = |char| Ident::AlphabeticChar(char);
+	let rational =
+		|numerator: i32, denominator: i32| BigRational::new(numerator.into(), denominator.into());
+
+	assert_eq!(
+		solve(&[r"x + y = 3", r"x - y = 1"]).unwrap(),
+		SystemSolution::Unique(vec![
+			(ident('x'), rational(2, 1)),
+			(ident('y'), rational(1, 1)),
+		])
+	);
+	// the unknowns are in the order they first appear
+	assert_eq!(
+		solve(&[r"2b = 1", r"a + b + c = 0", r"\frac{c}{3} = a"]).unwrap(),
+		SystemSolution::Unique(vec![
+			(ident('b'), rational(1, 2)),
+			(ident('a'), rational(-1, 8)),
+			(ident('c'), rational(-3, 8)),
+		])
+	);
+	assert_eq!(
+		solve(&[r"x + y = 1", r"2x + 2y = 3"]).unwrap(),
+		SystemSolution::None
+	);
+
+	let SystemSolution::Parametric { free, dependent } =
+		solve(&[r"x + y + z = 1", r"x - y = 0"]).unwrap()
+	else {
+		panic!("Expected a family of solutions");
+	};
+	assert_eq!(free, vec![ident('z')]);
+	let [(x, x_value), (y, y_value)] = <[_; 2]>::try_from(dependent).unwrap();
+	assert_eq!((x, y), (ident('x'), ident('y')));
+	assert_eq!(x_value, y_value);
+	assert_eq!(
+		x_value,
+		LinearForm {
+			coefficients: vec![rational(0, 1), rational(0, 1), rational(-1, 2)],
+			constant: rational(1, 2),
+		}
+	);
+
+	// the same equation twice is only one constraint
+	assert!(matches!(
+		solve(&[r"x + y = 2", r"2x + 2y = 4"]).unwrap(),
+		SystemSolution::Parametric { .. }
+	));
+
+	let Err(Error::InEquation { index: 1, err }) = solve(&[r"x = 1", r"xy = 2"]) else {
+		panic!("Expected the second equation to not be linear");
+	};
+	assert!(matches!(*err, Error::NotLinear));
+	assert!(matches!(solve(&[]), Err(Error::NoTokens)));
+}
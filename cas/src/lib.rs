@@ -12,25 +12,215 @@ pub mod prelude {
 pub mod contexts;
 
 pub mod storage {
-	use bevy_ecs::{bundle::Bundle, entity::Entity, world::World};
+	//! Lines of working out, each an entity in a [`World`]
+	//! linked to the line a pass rewrote it from
+
+	use std::borrow::Cow;
+
+	use bevy_ecs::{
+		bundle::Bundle,
+		component::Component,
+		entity::Entity,
+		system::{In, RunSystemOnce, System, SystemParamFunction, WithParamWarnPolicy},
+		world::World,
+	};
 
 	use crate::prelude::*;
 
 	pub struct Storage {
 		backing_world: World,
 		start: Entity,
+		latest: Entity,
+	}
+
+	/// What a pass returns to spawn a new line
+	pub struct Rewrite<B> {
+		pub line: B,
+		/// e.g. "subtract 1 from both sides"
+		pub description: String,
 	}
 
-	trait Context {
-		type Line: ContextLine;
+	/// A pass needing a resource that wasn't added with [`Storage::with_world`]
+	#[derive(thiserror::Error, Debug)]
+	#[error("The pass {pass} couldn't be run, it needs a resource that isn't in the world")]
+	pub struct MissingResource {
+		pub pass: Cow<'static, str>,
 	}
 
-	trait ContextLine {
-		// fn from_world
+	/// On every line but the first
+	#[derive(Component, Debug, Clone, PartialEq, Eq)]
+	pub struct Derived {
+		pub from: Entity,
+		/// The name of the pass's system
+		pub pass: Cow<'static, str>,
+		pub description: String,
+	}
+
+	impl Storage {
+		pub fn new(start: impl Bundle) -> Self {
+			Storage::with_world(World::new(), start)
+		}
+
+		/// For resources the passes need
+		pub fn with_world(mut world: World, start: impl Bundle) -> Self {
+			let start = world.spawn(start).id();
+			Storage {
+				backing_world: world,
+				start,
+				latest: start,
+			}
+		}
+
+		pub fn world(&self) -> &World {
+			&self.backing_world
+		}
+
+		pub fn start(&self) -> LineRef<'_> {
+			self.line(self.start)
+		}
+
+		pub fn latest(&self) -> LineRef<'_> {
+			self.line(self.latest)
+		}
+
+		fn line(&self, id: Entity) -> LineRef<'_> {
+			LineRef {
+				id,
+				world: &self.backing_world,
+			}
+		}
+
+		/// Runs `pass` with the latest line,
+		/// spawning each rewrite it returns as the new latest line in turn,
+		/// so a pass can return an [`Option`] or every step at once in a [`Vec`].
+		///
+		/// `Ok(None)` when the pass has nothing to rewrite,
+		/// and [`MissingResource`] if it can't be run
+		pub fn run_pass<B, R, E, M, F>(&mut self, pass: F) -> Result<Option<LineRef<'_>>, E>
+		where
+			B: Bundle,
+			R: IntoIterator<Item = Rewrite<B>>,
+			E: From<MissingResource>,
+			M: 'static,
+			F: SystemParamFunction<M, In = In<Entity>, Out = Result<R, E>>,
+		{
+			// otherwise a missing resource panics instead of failing to run
+			let pass = pass.never_param_warn();
+			let name = pass.name();
+			let rewrites = self
+				.backing_world
+				.run_system_once_with(self.latest, pass)
+				.map_err(|_| MissingResource { pass: name.clone() })??;
+			let from = self.latest;
+			for Rewrite { line, description } in rewrites {
+				self.latest = self
+					.backing_world
+					.spawn((
+						line,
+						Derived {
+							from: self.latest,
+							pass: name.clone(),
+							description,
+						},
+					))
+					.id();
+			}
+			Ok((self.latest != from).then(|| self.latest()))
+		}
+
+		/// Every line from the start to the latest,
+		/// following [`Derived::from`]
+		pub fn derivation(&self) -> Vec<LineRef<'_>> {
+			let mut lines: Vec<_> =
+				std::iter::successors(Some(self.latest()), |line| line.previous()).collect();
+			lines.reverse();
+			lines
+		}
 	}
 
+	#[derive(Clone, Copy)]
 	pub struct LineRef<'world> {
 		id: Entity,
 		world: &'world World,
 	}
+
+	impl<'world> LineRef<'world> {
+		pub fn id(&self) -> Entity {
+			self.id
+		}
+
+		pub fn get<C: Component>(&self) -> Option<&'world C> {
+			self.world.get(self.id)
+		}
+
+		/// `None` for the first line
+		pub fn derived(&self) -> Option<&'world Derived> {
+			self.get()
+		}
+
+		pub fn previous(&self) -> Option<LineRef<'world>> {
+			self.derived().map(|derived| LineRef {
+				id: derived.from,
+				world: self.world,
+			})
+		}
+	}
+
+	#[test]
+	fn passes_link_lines() {
+		#[derive(Component)]
+		struct Count(u32);
+
+		fn increment(
+			In(line): In<Entity>,
+			counts: bevy_ecs::system::Query<&Count>,
+		) -> Result<Option<Rewrite<Count>>, MissingResource> {
+			let Count(count) = counts.get(line).unwrap();
+			Ok((*count < 3).then(|| Rewrite {
+				line: Count(count + 1),
+				description: format!("add one to {}", count),
+			}))
+		}
+
+		let mut storage = Storage::new(Count(1));
+		while storage.run_pass(increment).unwrap().is_some() {}
+
+		let derivation = storage.derivation();
+		let counts: Vec<u32> = derivation
+			.iter()
+			.map(|line| line.get::<Count>().unwrap().0)
+			.collect();
+		assert_eq!(counts, vec![1, 2, 3]);
+		assert_eq!(derivation[0].id(), storage.start().id());
+		assert!(derivation[0].derived().is_none());
+		let derived = derivation[2].derived().unwrap();
+		assert_eq!(derived.from, derivation[1].id());
+		assert_eq!(derived.description, "add one to 2");
+		assert!(derived.pass.ends_with("increment"), "{}", derived.pass);
+	}
+
+	#[test]
+	fn passes_need_their_resources() {
+		#[derive(Component)]
+		struct Count(u32);
+		#[derive(bevy_ecs::system::Resource)]
+		struct Step(u32);
+
+		fn step(
+			In(line): In<Entity>,
+			counts: bevy_ecs::system::Query<&Count>,
+			step: bevy_ecs::system::Res<Step>,
+		) -> Result<Option<Rewrite<Count>>, MissingResource> {
+			let Count(count) = counts.get(line).unwrap();
+			Ok(Some(Rewrite {
+				line: Count(count + step.0),
+				description: format!("add {}", step.0),
+			}))
+		}
+
+		let mut storage = Storage::new(Count(1));
+		let err = storage.run_pass(step).err().unwrap();
+		assert!(err.pass.ends_with("step"), "{}", err.pass);
+		assert_eq!(storage.latest().id(), storage.start().id());
+	}
 }
@@ -0,0 +1,13 @@
+//! `cargo fuzz run parse_asciimath` from the `latex` directory
+
+#![no_main]
+
+use latex_parser::{LatexTokens, asciimath::parse_asciimath};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+	if let Ok(tokens) = parse_asciimath(input) {
+		let tokens: LatexTokens = tokens.into_iter().collect();
+		_ = tokens.to_latex();
+	}
+});
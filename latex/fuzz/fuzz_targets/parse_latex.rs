@@ -0,0 +1,25 @@
+//! `cargo fuzz run parse_latex` from the `latex` directory
+
+#![no_main]
+
+use latex_parser::{LatexTokens, Macros, parse_latex, parse_latex_lenient};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|latex: &str| {
+	let (lenient, errors) = parse_latex_lenient(latex);
+	for error in &errors {
+		assert!(latex.get(error.span().range()).is_some());
+	}
+	if let Ok(strict) = parse_latex(latex) {
+		assert!(errors.is_empty());
+		assert_eq!(lenient, strict);
+
+		let tokens: LatexTokens = strict.into_iter().collect();
+		_ = tokens.to_latex();
+		_ = tokens.to_mathml();
+		let json = serde_json::to_string(&tokens).unwrap();
+		let deserialized: LatexTokens = serde_json::from_str(&json).unwrap();
+		assert_eq!(deserialized.tokens(), tokens.tokens());
+	}
+	_ = LatexTokens::parse_from_latex_with_macros(latex, &Macros::new());
+});
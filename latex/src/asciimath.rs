@@ -0,0 +1,396 @@
+//! Plain text math like `2x^2 + 3/(x-1) = sqrt(5)`,
+//! mostly following [AsciiMath](https://asciimath.org).
+//!
+//! Parses into the same [`LatexToken`]s as the equivalent latex,
+//! so anything that takes [`LatexTokens`] works with either
+
+use nom::{
+	Parser,
+	branch::alt,
+	bytes::complete::take_till,
+	character::complete::{alphanumeric1, digit1, multispace0, satisfy},
+	combinator::{map, opt, recognize},
+	error::context,
+	multi::many1,
+	sequence::{delimited, preceded, terminated},
+};
+
+use crate::{
+	Bracket, Bracketed, Decimal, Error, Expected, Frac, Function, FunctionName, Greek, IResult,
+	Ident, Input, LatexToken, LatexTokens, MAX_DECIMAL_EXPONENT, RelationKind, Root, Spanned, State,
+	error::NomError, handle_parsing_errors_with, limited, spanned, tag, ws,
+};
+
+impl LatexTokens {
+	/// See [`parse_asciimath`]
+	pub fn parse_from_asciimath(input: &str) -> Result<Self, Error> {
+		parse_asciimath(input).map(LatexTokens)
+	}
+}
+
+/// Will error on invalid content at the end.
+///
+/// Spans are into `input`, not the equivalent latex
+pub fn parse_asciimath(input: &str) -> Result<Vec<Spanned<LatexToken>>, Error> {
+	let state = State::strict(input);
+	let res = ws(tokens).parse(Input::new_extra(input, &state));
+	handle_parsing_errors_with(res, item)
+}
+
+/// May leave whitespace or invalid content at the end
+fn tokens(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	map(many1(preceded(multispace0, item)), |items: Vec<_>| {
+		items.into_iter().flatten().collect()
+	})
+	.parse(input)
+}
+
+/// An operator, or a [`term`] which may be more than one token
+fn item(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	alt((map(spanned(operator), |operator| vec![operator]), term)).parse(input)
+}
+
+fn operator(input: Input) -> IResult<LatexToken> {
+	let symbol = |symbol, token: LatexToken| map(tag(symbol), move |_str| token.clone());
+	let relation =
+		|relation_symbol, relation| symbol(relation_symbol, LatexToken::Relation(relation));
+	alt((
+		symbol("+", LatexToken::Add),
+		symbol("-", LatexToken::Neg),
+		symbol("*", LatexToken::Mul),
+		symbol("·", LatexToken::Mul),
+		relation("!=", RelationKind::Ne),
+		relation("<=", RelationKind::Le),
+		relation(">=", RelationKind::Ge),
+		relation("~~", RelationKind::Approx),
+		relation("=", RelationKind::Eq),
+		relation("<", RelationKind::Lt),
+		relation(">", RelationKind::Gt),
+		relation("≠", RelationKind::Ne),
+		relation("≤", RelationKind::Le),
+		relation("≥", RelationKind::Ge),
+		relation("≈", RelationKind::Approx),
+	))
+	.parse(input)
+}
+
+/// Divides [`factor`]s, so `a/b/c` is `(a/b)/c`.
+///
+/// Brackets around either side are dropped, so `3/(x-1)` is `\frac{3}{x-1}`
+fn term(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	let (mut rest, mut numerator) = factor(input)?;
+	loop {
+		if !rest.trim_start().starts_with('/') {
+			return Ok((rest, numerator));
+		}
+		let (after, denominator) =
+			context("denominator", preceded(ws(tag("/")), spanned(factor))).parse(rest)?;
+		let span = numerator
+			.first()
+			.map_or(denominator.span, |first| first.span.join(denominator.span));
+		let frac = Frac {
+			numerator: unbracket(numerator),
+			denominator: unbracket(denominator.into_inner()),
+		};
+		numerator = vec![Spanned::new(LatexToken::Frac(frac), span)];
+		rest = after;
+	}
+}
+
+/// A [`simple`] expression and its exponent, if any, like `x^2` or `e^(-x)`
+fn factor(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	let exponent = map(
+		(
+			opt(spanned(map(tag("-"), |_str| LatexToken::Neg))),
+			spanned(simple),
+		),
+		|(neg, exponent)| match neg {
+			Some(neg) => vec![neg, exponent],
+			None => unbracket(vec![exponent]),
+		},
+	);
+	map(
+		(
+			spanned(simple),
+			opt(spanned(preceded(
+				ws(tag("^")),
+				context("exponent", exponent),
+			))),
+		),
+		|(base, exponent)| {
+			std::iter::once(base)
+				.chain(exponent.map(|exponent| exponent.map(LatexToken::Exp)))
+				.collect()
+		},
+	)
+	.parse(input)
+}
+
+/// `(x-1)` becomes `x-1`, which is only needed in plain text to group it
+fn unbracket(tokens: Vec<Spanned<LatexToken>>) -> Vec<Spanned<LatexToken>> {
+	match <[_; 1]>::try_from(tokens) {
+		Ok(
+			[
+				Spanned {
+					value:
+						LatexToken::Bracketed(Bracketed {
+							bracket: Bracket::Round,
+							inner,
+						}),
+					..
+				},
+			],
+		) => inner,
+		Ok(token) => token.into(),
+		Err(tokens) => tokens,
+	}
+}
+
+fn simple(input: Input) -> IResult<LatexToken> {
+	limited(alt((
+		number, text, brackets, abs, root, function, identifier,
+	)))
+	.parse(input)
+}
+
+/// Integers and decimals like `3.14`
+fn number(input: Input) -> IResult<LatexToken> {
+	let (rest, (whole, fraction)) = (digit1, opt(preceded(tag("."), digit1))).parse(input)?;
+	let fraction = fraction.map_or("", |fraction: Input| fraction.fragment());
+	let mantissa = format!("{}{}", whole, fraction)
+		.parse()
+		.expect("BigUint to parse from only digits");
+	if fraction.is_empty() {
+		return Ok((rest, LatexToken::Num(mantissa)));
+	}
+	match i64::try_from(fraction.len()) {
+		Ok(places) if places <= MAX_DECIMAL_EXPONENT => Ok((
+			rest,
+			LatexToken::Decimal(Decimal {
+				mantissa,
+				exponent: -places,
+			}),
+		)),
+		_ => Err(nom::Err::Failure(NomError::expected(
+			input,
+			Expected::Description("fewer decimal places"),
+		))),
+	}
+}
+
+/// `"kg"`, kept exactly
+fn text(input: Input) -> IResult<LatexToken> {
+	map(
+		preceded(
+			tag("\""),
+			terminated(take_till(|char| char == '"'), tag("\"")),
+		),
+		|text: Input| LatexToken::Text(text.to_string()),
+	)
+	.parse(input)
+}
+
+fn brackets(input: Input) -> IResult<LatexToken> {
+	let bracketed = |bracket, left, right| {
+		map(delimited(tag(left), tokens, ws(tag(right))), move |inner| {
+			LatexToken::Bracketed(Bracketed { bracket, inner })
+		})
+	};
+	context(
+		"brackets",
+		alt((
+			bracketed(Bracket::Round, "(", ")"),
+			bracketed(Bracket::Square, "[", "]"),
+			bracketed(Bracket::Curly, "{", "}"),
+		)),
+	)
+	.parse(input)
+}
+
+/// `|x|` or `abs(x)`
+fn abs(input: Input) -> IResult<LatexToken> {
+	context(
+		"absolute value",
+		alt((
+			delimited(tag("|"), tokens, ws(tag("|"))),
+			preceded(
+				ws(tag("abs")),
+				map(spanned(simple), |inner| unbracket(vec![inner])),
+			),
+		)),
+	)
+	.map(LatexToken::Abs)
+	.parse(input)
+}
+
+/// `sqrt(x)` or `root(3)(x)`
+fn root(input: Input) -> IResult<LatexToken> {
+	let argument = |name| context(name, map(spanned(simple), |inner| unbracket(vec![inner])));
+	context(
+		"root",
+		alt((
+			map(
+				preceded(ws(tag("sqrt")), argument("radicand")),
+				|radicand| Root {
+					index: None,
+					radicand,
+				},
+			),
+			map(
+				preceded(
+					ws(tag("root")),
+					(
+						argument("index"),
+						preceded(multispace0, argument("radicand")),
+					),
+				),
+				|(index, radicand)| Root {
+					index: Some(index),
+					radicand,
+				},
+			),
+		)),
+	)
+	.map(LatexToken::Root)
+	.parse(input)
+}
+
+/// `sin x`, `sin(x)`, `log_2(8)` or `sin^2 x`.
+///
+/// The argument is a [`factor`], so `sin x^2` is `\sin\left(x^{2}\right)`
+fn function(input: Input) -> IResult<LatexToken> {
+	let name = |input| {
+		word(
+			input,
+			FunctionName::ALL.map(|name| (name.name(), name)),
+			"a function like sin",
+		)
+	};
+	map(
+		(
+			name,
+			opt(preceded(
+				tag("_"),
+				map(spanned(simple), |subscript| unbracket(vec![subscript])),
+			)),
+			opt(preceded(
+				tag("^"),
+				map(spanned(simple), |power| unbracket(vec![power])),
+			)),
+			preceded(multispace0, context("argument", factor)),
+		),
+		|(name, subscript, power, argument)| {
+			LatexToken::Function(Function {
+				name,
+				subscript,
+				power,
+				argument: unbracket(argument),
+			})
+		},
+	)
+	.parse(input)
+}
+
+/// A letter like `x`, or a name like `pi` or `alpha`,
+/// with an optional subscript like `x_1` or `F_(net)`
+fn identifier(input: Input) -> IResult<LatexToken> {
+	let named = |input| {
+		let greek = Greek::all().map(|greek| (greek.name(), Ident::Greek(greek)));
+		let constants =
+			[("pi", Ident::Pi), ("tau", Ident::Tau)].map(|(name, ident)| (name.to_owned(), ident));
+		word(input, constants.into_iter().chain(greek), "a letter")
+	};
+	let letter = map(satisfy(char::is_alphabetic), Ident::AlphabeticChar);
+	let subscript = alt((
+		delimited(tag("("), alphanumeric1, tag(")")),
+		digit1,
+		recognize(satisfy(char::is_alphabetic)),
+	));
+	map(
+		(alt((named, letter)), opt(preceded(tag("_"), subscript))),
+		|(base, subscript)| match subscript {
+			Some(subscript) => Ident::Subscripted {
+				base: Box::new(base),
+				subscript: subscript.to_string(),
+			},
+			None => base,
+		},
+	)
+	.map(LatexToken::Ident)
+	.parse(input)
+}
+
+/// Matches one of `words`, trying longer ones first so `sinh` isn't `sin h`.
+///
+/// Like AsciiMath nothing needs to come between a word and what's after it,
+/// so `sinx` is `sin x`
+fn word<'i, T>(
+	input: Input<'i>,
+	words: impl IntoIterator<Item = (impl AsRef<str>, T)>,
+	expected: &'static str,
+) -> IResult<'i, T> {
+	let mut words: Vec<_> = words.into_iter().collect();
+	words.sort_by_key(|(word, _value)| std::cmp::Reverse(word.as_ref().len()));
+	for (word, value) in words {
+		if input.starts_with(word.as_ref()) {
+			let (rest, _word) = nom::Input::take_split(&input, word.as_ref().len());
+			return Ok((rest, value));
+		}
+	}
+	Err(nom::Err::Error(NomError::expected(
+		input,
+		Expected::Description(expected),
+	)))
+}
+
+#[test]
+fn asciimath() {
+	let same = |asciimath, latex| {
+		let parsed = LatexTokens::parse_from_asciimath(asciimath).unwrap();
+		let expected = LatexTokens::parse_from_latex(latex).unwrap();
+		assert_eq!(parsed.tokens(), expected.tokens(), "{}", asciimath);
+	};
+	same(
+		"2x^2 + 3/(x-1) = sqrt(5)",
+		r"2x^{2} + \frac{3}{x-1} = \sqrt{5}",
+	);
+	same(
+		"sin(x) * log_2 8 <= |alpha_1|",
+		r"\sin\left(x\right) \cdot \log_{2} 8 \le \left|\alpha_1\right|",
+	);
+	same(
+		"e^(-x) != root(3)(27) - 1.5",
+		r"e^{-x} \ne \sqrt[3]{27} - 1.5",
+	);
+	same(
+		"pi r^2 ~~ 3.14/2/x",
+		r"\pi r^{2} \approx \frac{\frac{3.14}{2}}{x}",
+	);
+	same(r#"F_(net) = 5 "N""#, r"F_{net} = 5 \text{N}");
+	same(
+		"cos^2 x + log_2^3(x)",
+		r"\cos^{2} x + \log_{2}^{3}\left(x\right)",
+	);
+	same(
+		"sinx^2 >= [a + b]",
+		r"\sin\left(x^{2}\right) \ge \left[a + b\right]",
+	);
+}
+
+#[test]
+fn asciimath_errors() {
+	let Err(Error::ParsingError(err)) = parse_asciimath("3/ = 2") else {
+		panic!("Expected a missing denominator to error");
+	};
+	assert_eq!(err.offset, 3);
+	assert_eq!(err.context, vec!["denominator"]);
+
+	let Err(Error::ParsingError(err)) = parse_asciimath(&format!("0.{}", "1".repeat(10_001))) else {
+		panic!("Expected too many decimal places to error");
+	};
+	assert!(
+		err
+			.expected
+			.contains(&Expected::Description("fewer decimal places"))
+	);
+}
@@ -0,0 +1,180 @@
+use nom::error::{ContextError, ErrorKind, ParseError};
+
+use crate::{Input, MacroError, Span};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+	#[error("Parsing error: {0}")]
+	ParsingError(ParsingError),
+
+	#[error("Macro error: {0}")]
+	Macro(MacroError),
+}
+
+impl Error {
+	/// Where in the source latex the error is
+	pub fn span(&self) -> Span {
+		match self {
+			Error::ParsingError(err) => err.span(),
+			Error::Macro(err) => err.span(),
+		}
+	}
+}
+
+impl From<ParsingError> for Error {
+	fn from(err: ParsingError) -> Self {
+		Error::ParsingError(err)
+	}
+}
+
+impl From<MacroError> for Error {
+	fn from(err: MacroError) -> Self {
+		Error::Macro(err)
+	}
+}
+
+/// Where parsing failed, and what would have been accepted there instead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsingError {
+	/// Byte offset into the source latex
+	pub offset: usize,
+	/// The character that couldn't be parsed,
+	/// `None` if the latex ended too early
+	pub found: Option<char>,
+	/// Every alternative that was tried at [`Self::offset`]
+	pub expected: Vec<Expected>,
+	/// What was being parsed, innermost first,
+	/// e.g. `["denominator", "fraction"]`
+	pub context: Vec<&'static str>,
+}
+
+impl ParsingError {
+	/// Covers [`Self::found`], so is empty at the end of the latex
+	pub fn span(&self) -> Span {
+		let len = self.found.map(char::len_utf8).unwrap_or(0);
+		Span::new(self.offset, self.offset + len)
+	}
+}
+
+impl std::fmt::Display for ParsingError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.found {
+			Some(found) => write!(f, "unexpected `{}` at {}", found, self.offset)?,
+			None => write!(f, "unexpected end of input at {}", self.offset)?,
+		}
+		if let Some((first, rest)) = self.expected.split_first() {
+			write!(f, ", expected {}", first)?;
+			for expected in rest {
+				write!(f, " or {}", expected)?;
+			}
+		}
+		for context in &self.context {
+			write!(f, ", in {}", context)?;
+		}
+		Ok(())
+	}
+}
+
+/// Something the parser would have accepted
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expected {
+	/// Exact latex, e.g. `\frac` or `}`
+	Literal(&'static str),
+	/// e.g. "a number"
+	Description(&'static str),
+	EndOfInput,
+}
+
+impl std::fmt::Display for Expected {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Expected::Literal(literal) => write!(f, "`{}`", literal),
+			Expected::Description(description) => write!(f, "{}", description),
+			Expected::EndOfInput => write!(f, "the end"),
+		}
+	}
+}
+
+/// The [`nom`] error type used by all the parsers in this crate.
+///
+/// When alternatives fail, keeps whichever got furthest,
+/// and merges what was expected if they got equally far
+#[derive(Debug)]
+pub(crate) struct NomError<'i> {
+	input: Input<'i>,
+	expected: Vec<Expected>,
+	context: Vec<&'static str>,
+}
+
+impl<'i> NomError<'i> {
+	pub(crate) fn expected(input: Input<'i>, expected: Expected) -> Self {
+		NomError {
+			input,
+			expected: vec![expected],
+			context: vec![],
+		}
+	}
+
+	pub(crate) fn offset(&self) -> usize {
+		self.input.location_offset()
+	}
+}
+
+impl<'i> ParseError<Input<'i>> for NomError<'i> {
+	fn from_error_kind(input: Input<'i>, kind: ErrorKind) -> Self {
+		let expected = match kind {
+			ErrorKind::Digit => vec![Expected::Description("a number")],
+			ErrorKind::Eof => vec![Expected::EndOfInput],
+			_ => vec![],
+		};
+		NomError {
+			input,
+			expected,
+			context: vec![],
+		}
+	}
+
+	fn append(_input: Input<'i>, _kind: ErrorKind, other: Self) -> Self {
+		other
+	}
+
+	fn or(mut self, other: Self) -> Self {
+		match self.offset().cmp(&other.offset()) {
+			std::cmp::Ordering::Less => other,
+			std::cmp::Ordering::Greater => self,
+			std::cmp::Ordering::Equal => {
+				for expected in other.expected {
+					if !self.expected.contains(&expected) {
+						self.expected.push(expected);
+					}
+				}
+				if self.context.is_empty() {
+					self.context = other.context;
+				}
+				self
+			}
+		}
+	}
+}
+
+impl<'i> ContextError<Input<'i>> for NomError<'i> {
+	/// Only if `other` got past the start of `input`,
+	/// otherwise every alternative would add its context
+	fn add_context(input: Input<'i>, context: &'static str, mut other: Self) -> Self {
+		if other.offset() > input.location_offset() {
+			other.context.push(context);
+		}
+		other
+	}
+}
+
+impl From<NomError<'_>> for ParsingError {
+	fn from(err: NomError<'_>) -> Self {
+		ParsingError {
+			offset: err.offset(),
+			found: err.input.fragment().chars().next(),
+			expected: err.expected,
+			context: err.context,
+		}
+	}
+}
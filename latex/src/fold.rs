@@ -0,0 +1,198 @@
+use num::BigUint;
+
+use crate::{
+	BigOp, Bracketed, Decimal, Frac, Function, Ident, Integral, LatexToken, LatexTokens, Limit,
+	Matrix, RelationKind, Root, Span, Spanned,
+};
+
+/// Rebuilds owned tokens, e.g. for a normalization pass.
+///
+/// Every method defaults to keeping the token, folding any tokens inside it,
+/// so only the tokens that change need overriding.
+/// Unlike [`crate::TokenVisitor`], bodies are folded with their bound variables
+pub trait TokenFold {
+	/// Default folds each token,
+	/// override to add, remove or merge tokens next to each other
+	fn fold_tokens(&mut self, tokens: Vec<Spanned<LatexToken>>) -> Vec<Spanned<LatexToken>> {
+		tokens
+			.into_iter()
+			.map(|token| token.map(|token| token.fold(self)))
+			.collect()
+	}
+	fn fold_num(&mut self, num: BigUint) -> LatexToken {
+		LatexToken::Num(num)
+	}
+	fn fold_decimal(&mut self, decimal: Decimal) -> LatexToken {
+		LatexToken::Decimal(decimal)
+	}
+	fn fold_neg(&mut self) -> LatexToken {
+		LatexToken::Neg
+	}
+	fn fold_mul(&mut self) -> LatexToken {
+		LatexToken::Mul
+	}
+	fn fold_add(&mut self) -> LatexToken {
+		LatexToken::Add
+	}
+	fn fold_relation(&mut self, relation: RelationKind) -> LatexToken {
+		LatexToken::Relation(relation)
+	}
+	fn fold_exp(&mut self, exp: Vec<Spanned<LatexToken>>) -> LatexToken {
+		LatexToken::Exp(self.fold_tokens(exp))
+	}
+	/// Bound variables like the `i` in `\sum_{i=1}^{n}` aren't folded,
+	/// as they can only be an [`Ident`]
+	fn fold_ident(&mut self, ident: Ident) -> LatexToken {
+		LatexToken::Ident(ident)
+	}
+	fn fold_bracketed(&mut self, bracketed: Bracketed) -> LatexToken {
+		LatexToken::Bracketed(Bracketed {
+			bracket: bracketed.bracket,
+			inner: self.fold_tokens(bracketed.inner),
+		})
+	}
+	fn fold_abs(&mut self, abs: Vec<Spanned<LatexToken>>) -> LatexToken {
+		LatexToken::Abs(self.fold_tokens(abs))
+	}
+	fn fold_frac(&mut self, frac: Frac) -> LatexToken {
+		LatexToken::Frac(Frac {
+			numerator: self.fold_tokens(frac.numerator),
+			denominator: self.fold_tokens(frac.denominator),
+		})
+	}
+	fn fold_root(&mut self, root: Root) -> LatexToken {
+		LatexToken::Root(Root {
+			index: root.index.map(|index| self.fold_tokens(index)),
+			radicand: self.fold_tokens(root.radicand),
+		})
+	}
+	fn fold_function(&mut self, function: Function) -> LatexToken {
+		LatexToken::Function(Function {
+			name: function.name,
+			subscript: function
+				.subscript
+				.map(|subscript| self.fold_tokens(subscript)),
+			power: function.power.map(|power| self.fold_tokens(power)),
+			argument: self.fold_tokens(function.argument),
+		})
+	}
+	fn fold_big_op(&mut self, big_op: BigOp) -> LatexToken {
+		LatexToken::BigOp(BigOp {
+			kind: big_op.kind,
+			variable: big_op.variable,
+			lower: big_op.lower.map(|lower| self.fold_tokens(lower)),
+			upper: big_op.upper.map(|upper| self.fold_tokens(upper)),
+			body: self.fold_tokens(big_op.body),
+		})
+	}
+	fn fold_integral(&mut self, integral: Integral) -> LatexToken {
+		LatexToken::Integral(Integral {
+			lower: integral.lower.map(|lower| self.fold_tokens(lower)),
+			upper: integral.upper.map(|upper| self.fold_tokens(upper)),
+			body: self.fold_tokens(integral.body),
+			variable: integral.variable,
+		})
+	}
+	fn fold_limit(&mut self, limit: Limit) -> LatexToken {
+		LatexToken::Limit(Limit {
+			variable: limit.variable,
+			approaching: self.fold_tokens(limit.approaching),
+			body: self.fold_tokens(limit.body),
+		})
+	}
+	fn fold_matrix(&mut self, matrix: Matrix) -> LatexToken {
+		LatexToken::Matrix(Matrix {
+			kind: matrix.kind,
+			rows: matrix
+				.rows
+				.into_iter()
+				.map(|row| row.into_iter().map(|cell| self.fold_tokens(cell)).collect())
+				.collect(),
+		})
+	}
+	fn fold_text(&mut self, text: String) -> LatexToken {
+		LatexToken::Text(text)
+	}
+	fn fold_error(&mut self, span: Span) -> LatexToken {
+		LatexToken::Error(span)
+	}
+}
+
+impl LatexToken {
+	pub fn fold<T>(self, folder: &mut T) -> LatexToken
+	where
+		T: TokenFold + ?Sized,
+	{
+		match self {
+			LatexToken::Num(num) => folder.fold_num(num),
+			LatexToken::Decimal(decimal) => folder.fold_decimal(decimal),
+			LatexToken::Neg => folder.fold_neg(),
+			LatexToken::Mul => folder.fold_mul(),
+			LatexToken::Add => folder.fold_add(),
+			LatexToken::Relation(relation) => folder.fold_relation(relation),
+			LatexToken::Exp(exp) => folder.fold_exp(exp),
+			LatexToken::Ident(ident) => folder.fold_ident(ident),
+			LatexToken::Bracketed(bracketed) => folder.fold_bracketed(bracketed),
+			LatexToken::Abs(abs) => folder.fold_abs(abs),
+			LatexToken::Frac(frac) => folder.fold_frac(frac),
+			LatexToken::Root(root) => folder.fold_root(root),
+			LatexToken::Function(function) => folder.fold_function(function),
+			LatexToken::BigOp(big_op) => folder.fold_big_op(big_op),
+			LatexToken::Integral(integral) => folder.fold_integral(integral),
+			LatexToken::Limit(limit) => folder.fold_limit(limit),
+			LatexToken::Matrix(matrix) => folder.fold_matrix(matrix),
+			LatexToken::Text(text) => folder.fold_text(text),
+			LatexToken::Error(span) => folder.fold_error(span),
+		}
+	}
+}
+
+impl LatexTokens {
+	pub fn fold<T>(self, folder: &mut T) -> LatexTokens
+	where
+		T: TokenFold + ?Sized,
+	{
+		LatexTokens(folder.fold_tokens(self.0))
+	}
+}
+
+#[test]
+fn fold() {
+	/// `\tau` becomes `2\pi`, which needs [`TokenFold::fold_tokens`]
+	/// as one token becomes two
+	struct ExpandTau;
+	impl TokenFold for ExpandTau {
+		fn fold_tokens(&mut self, tokens: Vec<Spanned<LatexToken>>) -> Vec<Spanned<LatexToken>> {
+			tokens
+				.into_iter()
+				.flat_map(|token| match token.value {
+					LatexToken::Ident(Ident::Tau) => vec![
+						Spanned::new(LatexToken::Num(2u32.into()), token.span),
+						Spanned::new(LatexToken::Ident(Ident::Pi), token.span),
+					],
+					_ => vec![token.map(|token| token.fold(self))],
+				})
+				.collect()
+		}
+	}
+
+	let tokens = LatexTokens::parse_from_latex(r"\frac{\tau}{2} + \sin\left(\tau x\right)").unwrap();
+	let expected =
+		LatexTokens::parse_from_latex(r"\frac{2\pi}{2} + \sin\left(2\pi x\right)").unwrap();
+	assert_eq!(tokens.fold(&mut ExpandTau).tokens(), expected.tokens());
+
+	/// Only changes one kind of token
+	struct Round;
+	impl TokenFold for Round {
+		fn fold_decimal(&mut self, decimal: Decimal) -> LatexToken {
+			let mut num = decimal.mantissa;
+			for _ in decimal.exponent..0 {
+				num /= 10u32;
+			}
+			LatexToken::Num(num)
+		}
+	}
+	let tokens = LatexTokens::parse_from_latex(r"x^{2.5} = |1.75|").unwrap();
+	let expected = LatexTokens::parse_from_latex(r"x^{2} = |1|").unwrap();
+	assert_eq!(tokens.fold(&mut Round).tokens(), expected.tokens());
+}
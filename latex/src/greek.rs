@@ -0,0 +1,247 @@
+/// A greek letter with a latex command, other than `\pi` and `\tau`,
+/// which are always [`crate::Ident::Pi`] and [`crate::Ident::Tau`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GreekFields"))]
+pub struct Greek {
+	letter: GreekLetter,
+	uppercase: bool,
+}
+
+impl Greek {
+	/// `None` if there's no latex command for it, like `\Alpha`,
+	/// or if it's `\pi` or `\tau`
+	pub fn new(letter: GreekLetter, uppercase: bool) -> Option<Greek> {
+		let has_command = match uppercase {
+			true => letter.has_uppercase(),
+			false => !matches!(letter, GreekLetter::Pi | GreekLetter::Tau),
+		};
+		has_command.then_some(Greek { letter, uppercase })
+	}
+
+	/// Every letter [`Greek::new`] accepts
+	pub fn all() -> impl Iterator<Item = Greek> {
+		GreekLetter::ALL.into_iter().flat_map(|letter| {
+			[false, true]
+				.into_iter()
+				.filter_map(move |uppercase| Greek::new(letter, uppercase))
+		})
+	}
+
+	pub fn letter(&self) -> GreekLetter {
+		self.letter
+	}
+
+	/// Only some letters have an uppercase latex command,
+	/// see [`GreekLetter::has_uppercase`]
+	pub fn uppercase(&self) -> bool {
+		self.uppercase
+	}
+
+	/// Without the leading `\`, e.g. `alpha` or `Omega`
+	pub fn name(&self) -> String {
+		let name = self.letter.name();
+		if self.uppercase {
+			let mut chars = name.chars();
+			chars
+				.next()
+				.map(|first| first.to_ascii_uppercase())
+				.into_iter()
+				.chain(chars)
+				.collect()
+		} else {
+			name.to_owned()
+		}
+	}
+}
+
+impl std::fmt::Display for Greek {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let (lowercase, uppercase) = self.letter.chars();
+		if self.uppercase {
+			write!(f, "{}", uppercase)
+		} else {
+			write!(f, "{}", lowercase)
+		}
+	}
+}
+
+/// What [`Greek`] deserializes from, before checking it has a latex command
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GreekFields {
+	letter: GreekLetter,
+	uppercase: bool,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GreekFields> for Greek {
+	type Error = String;
+
+	fn try_from(GreekFields { letter, uppercase }: GreekFields) -> Result<Self, Self::Error> {
+		Greek::new(letter, uppercase).ok_or_else(|| {
+			format!(
+				"no latex command for {} {}",
+				if uppercase { "uppercase" } else { "lowercase" },
+				letter.name()
+			)
+		})
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GreekLetter {
+	Alpha,
+	Beta,
+	Gamma,
+	Delta,
+	Epsilon,
+	Zeta,
+	Eta,
+	Theta,
+	Iota,
+	Kappa,
+	Lambda,
+	Mu,
+	Nu,
+	Xi,
+	Omicron,
+	Pi,
+	Rho,
+	Sigma,
+	Tau,
+	Upsilon,
+	Phi,
+	Chi,
+	Psi,
+	Omega,
+}
+
+impl GreekLetter {
+	pub const ALL: [GreekLetter; 24] = [
+		GreekLetter::Alpha,
+		GreekLetter::Beta,
+		GreekLetter::Gamma,
+		GreekLetter::Delta,
+		GreekLetter::Epsilon,
+		GreekLetter::Zeta,
+		GreekLetter::Eta,
+		GreekLetter::Theta,
+		GreekLetter::Iota,
+		GreekLetter::Kappa,
+		GreekLetter::Lambda,
+		GreekLetter::Mu,
+		GreekLetter::Nu,
+		GreekLetter::Xi,
+		GreekLetter::Omicron,
+		GreekLetter::Pi,
+		GreekLetter::Rho,
+		GreekLetter::Sigma,
+		GreekLetter::Tau,
+		GreekLetter::Upsilon,
+		GreekLetter::Phi,
+		GreekLetter::Chi,
+		GreekLetter::Psi,
+		GreekLetter::Omega,
+	];
+
+	/// Lowercase, without the leading `\`
+	pub fn name(self) -> &'static str {
+		match self {
+			GreekLetter::Alpha => "alpha",
+			GreekLetter::Beta => "beta",
+			GreekLetter::Gamma => "gamma",
+			GreekLetter::Delta => "delta",
+			GreekLetter::Epsilon => "epsilon",
+			GreekLetter::Zeta => "zeta",
+			GreekLetter::Eta => "eta",
+			GreekLetter::Theta => "theta",
+			GreekLetter::Iota => "iota",
+			GreekLetter::Kappa => "kappa",
+			GreekLetter::Lambda => "lambda",
+			GreekLetter::Mu => "mu",
+			GreekLetter::Nu => "nu",
+			GreekLetter::Xi => "xi",
+			GreekLetter::Omicron => "omicron",
+			GreekLetter::Pi => "pi",
+			GreekLetter::Rho => "rho",
+			GreekLetter::Sigma => "sigma",
+			GreekLetter::Tau => "tau",
+			GreekLetter::Upsilon => "upsilon",
+			GreekLetter::Phi => "phi",
+			GreekLetter::Chi => "chi",
+			GreekLetter::Psi => "psi",
+			GreekLetter::Omega => "omega",
+		}
+	}
+
+	/// Latex only has commands like `\Gamma` for the uppercase letters
+	/// that don't look like latin ones
+	pub fn has_uppercase(self) -> bool {
+		matches!(
+			self,
+			GreekLetter::Gamma
+				| GreekLetter::Delta
+				| GreekLetter::Theta
+				| GreekLetter::Lambda
+				| GreekLetter::Xi
+				| GreekLetter::Pi
+				| GreekLetter::Sigma
+				| GreekLetter::Upsilon
+				| GreekLetter::Phi
+				| GreekLetter::Psi
+				| GreekLetter::Omega
+		)
+	}
+
+	/// (lowercase, uppercase)
+	fn chars(self) -> (char, char) {
+		match self {
+			GreekLetter::Alpha => ('α', 'Α'),
+			GreekLetter::Beta => ('β', 'Β'),
+			GreekLetter::Gamma => ('γ', 'Γ'),
+			GreekLetter::Delta => ('δ', 'Δ'),
+			GreekLetter::Epsilon => ('ε', 'Ε'),
+			GreekLetter::Zeta => ('ζ', 'Ζ'),
+			GreekLetter::Eta => ('η', 'Η'),
+			GreekLetter::Theta => ('θ', 'Θ'),
+			GreekLetter::Iota => ('ι', 'Ι'),
+			GreekLetter::Kappa => ('κ', 'Κ'),
+			GreekLetter::Lambda => ('λ', 'Λ'),
+			GreekLetter::Mu => ('μ', 'Μ'),
+			GreekLetter::Nu => ('ν', 'Ν'),
+			GreekLetter::Xi => ('ξ', 'Ξ'),
+			GreekLetter::Omicron => ('ο', 'Ο'),
+			GreekLetter::Pi => ('π', 'Π'),
+			GreekLetter::Rho => ('ρ', 'Ρ'),
+			GreekLetter::Sigma => ('σ', 'Σ'),
+			GreekLetter::Tau => ('τ', 'Τ'),
+			GreekLetter::Upsilon => ('υ', 'Υ'),
+			GreekLetter::Phi => ('φ', 'Φ'),
+			GreekLetter::Chi => ('χ', 'Χ'),
+			GreekLetter::Psi => ('ψ', 'Ψ'),
+			GreekLetter::Omega => ('ω', 'Ω'),
+		}
+	}
+}
+
+#[test]
+fn greek_letters_have_commands() {
+	assert!(Greek::new(GreekLetter::Alpha, true).is_none());
+	assert!(Greek::new(GreekLetter::Pi, false).is_none());
+	assert!(Greek::new(GreekLetter::Tau, false).is_none());
+	assert_eq!(Greek::new(GreekLetter::Pi, true).unwrap().name(), "Pi");
+	// every one parses back the same
+	for greek in Greek::all() {
+		let latex = format!(r"\{}", greek.name());
+		let tokens = crate::LatexTokens::parse_from_latex(&latex).unwrap();
+		assert_eq!(
+			tokens.tokens()[0].value,
+			crate::LatexToken::Ident(crate::Ident::Greek(greek)),
+			"{}",
+			latex
+		);
+	}
+}
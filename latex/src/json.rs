@@ -0,0 +1,152 @@
+//! The JSON shape of [`LatexTokens`], with the `serde` feature.
+//!
+//! [`LatexTokens`] is wrapped with the [`VERSION`] of the shape,
+//! which changes whenever old JSON would no longer deserialize the same,
+//! and deserializing a different version errors:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "tokens": [
+//!     { "span": { "start": 0, "end": 1 }, "value": { "type": "num", "data": "2" } },
+//!     { "span": { "start": 1, "end": 4 }, "value": { "type": "ident", "data": { "type": "pi" } } },
+//!     { "span": { "start": 5, "end": 6 }, "value": { "type": "neg" } },
+//!     {
+//!       "span": { "start": 7, "end": 10 },
+//!       "value": {
+//!         "type": "decimal",
+//!         "data": { "mantissa": "15", "exponent": -1 }
+//!       }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! - Every token is the `span` of latex it was parsed from and its `value`.
+//! - [`LatexToken`] and [`Ident`] are objects with their variant as `type`
+//!   in snake case, and anything inside as `data`.
+//! - Structs like [`Frac`] are objects with their fields in snake case.
+//! - Other enums like [`RelationKind`] are just their variant in snake case, e.g. `"le"`.
+//! - Numbers like [`LatexToken::Num`] and [`Decimal::mantissa`] are decimal strings,
+//!   as they can be too big for a JSON number.
+//!
+//! [`Ident`]: crate::Ident
+//! [`Frac`]: crate::Frac
+//! [`RelationKind`]: crate::RelationKind
+//! [`Decimal::mantissa`]: crate::Decimal::mantissa
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+
+use crate::{LatexToken, LatexTokens, Spanned};
+
+/// Of the JSON shape, see the [module docs](self)
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+	version: u32,
+	tokens: T,
+}
+
+impl Serialize for LatexTokens {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		Versioned {
+			version: VERSION,
+			tokens: &self.0,
+		}
+		.serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for LatexTokens {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let versioned = Versioned::<Vec<Spanned<LatexToken>>>::deserialize(deserializer)?;
+		if versioned.version != VERSION {
+			return Err(D::Error::custom(format!(
+				"unsupported LatexTokens version {}, expected {}",
+				versioned.version, VERSION
+			)));
+		}
+		Ok(LatexTokens(versioned.tokens))
+	}
+}
+
+/// [`num::BigUint`] as a string of decimal digits
+pub(crate) mod biguint {
+	use num::BigUint;
+	use serde::{Deserialize, Deserializer, Serializer, de::Error as _};
+
+	pub fn serialize<S: Serializer>(num: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(num)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+		let digits = std::borrow::Cow::<str>::deserialize(deserializer)?;
+		if digits.is_empty() || !digits.chars().all(|char| char.is_ascii_digit()) {
+			return Err(D::Error::custom(format!(
+				"expected a string of decimal digits, found {:?}",
+				digits
+			)));
+		}
+		Ok(digits.parse().expect("BigUint to parse from only digits"))
+	}
+}
+
+#[test]
+fn json() {
+	let tokens = LatexTokens::parse_from_latex(r"2\pi - 1.5").unwrap();
+	let json = serde_json::to_string(&tokens).unwrap();
+	insta::assert_snapshot!(
+		json,
+		@r#"{"version":1,"tokens":[{"span":{"start":0,"end":1},"value":{"type":"num","data":"2"}},{"span":{"start":1,"end":4},"value":{"type":"ident","data":{"type":"pi"}}},{"span":{"start":5,"end":6},"value":{"type":"neg"}},{"span":{"start":7,"end":10},"value":{"type":"decimal","data":{"mantissa":"15","exponent":-1}}}]}"#
+	);
+
+	let latex = r"\sum_{i=1}^{n} \frac{x_1}{\sqrt[3]{y}} \le \sin^{2}\left(\alpha\right) + \begin{pmatrix} 1 \\ 2 \end{pmatrix} + 123456789012345678901234567890";
+	let tokens = LatexTokens::parse_from_latex(latex).unwrap();
+	let json = serde_json::to_string(&tokens).unwrap();
+	let deserialized: LatexTokens = serde_json::from_str(&json).unwrap();
+	assert_eq!(deserialized.tokens(), tokens.tokens());
+	let spans = |tokens: &LatexTokens| {
+		tokens
+			.tokens()
+			.iter()
+			.map(|token| token.span)
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(spans(&deserialized), spans(&tokens));
+
+	let err = serde_json::from_str::<LatexTokens>(r#"{"version":2,"tokens":[]}"#).unwrap_err();
+	assert!(
+		err
+			.to_string()
+			.contains("unsupported LatexTokens version 2")
+	);
+	let err = serde_json::from_str::<LatexTokens>(
+		r#"{"version":1,"tokens":[{"value":{"type":"num","data":"-1"},"span":{"start":0,"end":2}}]}"#,
+	)
+	.unwrap_err();
+	assert!(err.to_string().contains("decimal digits"));
+
+	// from before functions had powers
+	let tokens: LatexTokens = serde_json::from_str(
+		r#"{"version":1,"tokens":[{"span":{"start":0,"end":6},"value":{"type":"function","data":{"name":"sin","subscript":null,"argument":[]}}}]}"#,
+	)
+	.unwrap();
+	let [
+		Spanned {
+			value: LatexToken::Function(function),
+			..
+		},
+	] = tokens.tokens()
+	else {
+		panic!("Expected a function");
+	};
+	assert_eq!(function.power, None);
+
+	// no `\Alpha` command
+	let err = serde_json::from_str::<LatexTokens>(
+		r#"{"version":1,"tokens":[{"span":{"start":0,"end":6},"value":{"type":"ident","data":{"type":"greek","data":{"letter":"alpha","uppercase":true}}}}]}"#,
+	)
+	.unwrap_err();
+	assert!(err.to_string().contains("no latex command"), "{}", err);
+}
@@ -1,31 +1,78 @@
 #[allow(unused_imports)]
 use nom::{
-	Finish, Parser,
+	Parser,
 	branch::alt,
-	bytes::{
-		complete::{tag, take_while1},
-		take_while,
-	},
-	character::complete::{alpha1, anychar, digit1, multispace0},
-	combinator::{all_consuming, map},
-	error::ParseError,
-	multi::{many0, many1},
-	sequence::{delimited, preceded},
+	bytes::complete::take_till,
+	character::complete::{alphanumeric1, digit1, multispace0, satisfy},
+	combinator::{eof, map, map_opt, opt, peek, success, value},
+	error::{ParseError, context},
+	multi::{many_till, many0, many1, separated_list1},
+	sequence::{delimited, preceded, terminated},
 };
-use nom_language::error::VerboseError;
 use num::BigUint;
+use std::cell::{Cell, RefCell};
+
+pub use error::{Error, Expected, ParsingError};
+pub use fold::TokenFold;
+pub use greek::{Greek, GreekLetter};
+pub use macros::{Macro, MacroError, Macros};
+pub use span::{Span, Spanned};
+pub use visit_mut::TokenVisitorMut;
+
+use error::NomError;
+
+pub mod asciimath;
+mod error;
+mod fold;
+mod greek;
+#[cfg(feature = "serde")]
+pub mod json;
+mod macros;
+pub mod mathml;
+mod span;
+#[cfg(test)]
+mod strategies;
+mod to_latex;
+mod visit_mut;
 
 #[derive(Debug)]
-pub struct LatexTokens(Vec<LatexToken>);
+pub struct LatexTokens(Vec<Spanned<LatexToken>>);
 
 impl LatexTokens {
+	/// Also expands any macros defined with `\newcommand`,
+	/// see [`Self::parse_from_latex_with_macros`]
 	pub fn parse_from_latex(latex: &str) -> Result<Self, Error> {
-		parse_latex(latex).map(LatexTokens)
+		Self::parse_from_latex_with_macros(latex, &Macros::default())
+	}
+
+	/// Expands `macros` before parsing.
+	///
+	/// Spans are into `latex`, with whatever a macro expanded to
+	/// spanning where the macro was used
+	pub fn parse_from_latex_with_macros(latex: &str, macros: &Macros) -> Result<Self, Error> {
+		let expanded = macros.expand_mapped(latex)?;
+		let mut tokens = match parse_latex(&expanded.latex) {
+			Ok(tokens) => LatexTokens(tokens),
+			Err(Error::ParsingError(err)) => return Err(expanded.source_error(err).into()),
+			Err(err) => return Err(err),
+		};
+		tokens.visit_mut(&mut &expanded);
+		Ok(tokens)
+	}
+
+	/// See [`parse_latex_lenient`]
+	pub fn parse_from_latex_lenient(latex: &str) -> (Self, Vec<ParsingError>) {
+		let (tokens, errors) = parse_latex_lenient(latex);
+		(LatexTokens(tokens), errors)
+	}
+
+	pub fn tokens(&self) -> &[Spanned<LatexToken>] {
+		&self.0
 	}
 
 	pub fn visit<T>(&self, visitor: &mut T)
 	where
-		T: TokenVisitor,
+		T: TokenVisitor + ?Sized,
 	{
 		for token in &self.0 {
 			token.visit(visitor);
@@ -34,20 +81,50 @@ impl LatexTokens {
 }
 
 /// Will error on invalid content at the end
-pub fn parse_latex(input: &str) -> Result<Vec<LatexToken>, Error> {
-	let res = all_consuming(ws(tokens)).parse(input).finish();
-	let tokens = Error::handle_parsing_errors(res, input)?;
+pub fn parse_latex(input: &str) -> Result<Vec<Spanned<LatexToken>>, Error> {
+	let state = State::strict(input);
+	let res = ws(tokens).parse(Input::new_extra(input, &state));
+	let tokens = handle_parsing_errors(res)?;
 	Ok(tokens)
 }
 
-impl FromIterator<LatexToken> for LatexTokens {
-	fn from_iter<T: IntoIterator<Item = LatexToken>>(iter: T) -> Self {
+/// Never fails, instead replacing whatever couldn't be parsed
+/// with [`LatexToken::Error`] and returning every error found,
+/// for showing incomplete latex while it's being typed.
+///
+/// Empty latex has no tokens and no errors
+pub fn parse_latex_lenient(input: &str) -> (Vec<Spanned<LatexToken>>, Vec<ParsingError>) {
+	let state = State::lenient(input);
+	let recovered = state.recovered.as_ref().expect("lenient state to recover");
+	let mut input = Input::new_extra(input, &state);
+	let mut tokens = Vec::new();
+	loop {
+		let (rest, parsed) = lenient_tokens(input, recovered);
+		tokens.extend(parsed);
+		if rest.is_empty() {
+			break;
+		}
+		// a closing bracket that was never opened
+		let (after, span) = skip_invalid(rest);
+		if push_error(&mut tokens, span) {
+			let err = NomError::expected(rest, Expected::EndOfInput);
+			recovered.borrow_mut().push(err.into());
+		}
+		input = after;
+	}
+	let mut errors = recovered.take();
+	errors.sort_by_key(|err| err.offset);
+	(tokens, errors)
+}
+
+impl FromIterator<Spanned<LatexToken>> for LatexTokens {
+	fn from_iter<T: IntoIterator<Item = Spanned<LatexToken>>>(iter: T) -> Self {
 		LatexTokens(iter.into_iter().collect())
 	}
 }
 
 impl IntoIterator for LatexTokens {
-	type Item = LatexToken;
+	type Item = Spanned<LatexToken>;
 	type IntoIter = std::vec::IntoIter<Self::Item>;
 
 	fn into_iter(self) -> Self::IntoIter {
@@ -56,17 +133,39 @@ impl IntoIterator for LatexTokens {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+	feature = "serde",
+	serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
 pub enum LatexToken {
+	#[cfg_attr(feature = "serde", serde(with = "json::biguint"))]
 	Num(BigUint),
+	/// Only if written with a decimal point
+	/// or in scientific notation
+	Decimal(Decimal),
 	Neg,
 	Mul,
 	Add,
-	Eq,
+	Relation(RelationKind),
 	/// May be empty
-	Exp(Vec<LatexToken>),
+	Exp(Vec<Spanned<LatexToken>>),
 	Ident(Ident),
 	Bracketed(Bracketed),
+	/// `\left|x\right|` or `|x|`
+	Abs(Vec<Spanned<LatexToken>>),
 	Frac(Frac),
+	Root(Root),
+	Function(Function),
+	BigOp(BigOp),
+	Integral(Integral),
+	Limit(Limit),
+	Matrix(Matrix),
+	/// `\text{kg}`, e.g. a unit or label
+	Text(String),
+	/// Latex that couldn't be parsed,
+	/// only from [`parse_latex_lenient`]
+	Error(Span),
 }
 
 impl From<BigUint> for LatexToken {
@@ -75,18 +174,23 @@ impl From<BigUint> for LatexToken {
 	}
 }
 
-/// IDK why this needs to be sized btw
-pub trait TokenVisitor: Sized {
+/// Observes tokens, see [`TokenVisitorMut`] and [`TokenFold`] to change them
+pub trait TokenVisitor {
 	fn visit_num(&mut self, num: &BigUint) {
 		_ = num;
 	}
+	fn visit_decimal(&mut self, decimal: &Decimal) {
+		_ = decimal;
+	}
 	fn visit_neg(&mut self) {}
 	fn visit_mul(&mut self) {}
 	fn visit_add(&mut self) {}
-	fn visit_exp(&mut self, exp: &[LatexToken]) {
+	fn visit_exp(&mut self, exp: &[Spanned<LatexToken>]) {
 		_ = exp;
 	}
-	fn visit_eq(&mut self) {}
+	fn visit_relation(&mut self, relation: &RelationKind) {
+		_ = relation;
+	}
 	fn visit_ident(&mut self, ident: &Ident) {
 		_ = ident;
 	}
@@ -96,6 +200,46 @@ pub trait TokenVisitor: Sized {
 			token.visit(self);
 		}
 	}
+	/// Default visits inner tokens
+	fn visit_abs(&mut self, abs: &[Spanned<LatexToken>]) {
+		for token in abs {
+			token.visit(self);
+		}
+	}
+	/// Default visits bounds, then body without uses of the bound variable
+	fn visit_big_op(&mut self, big_op: &BigOp) {
+		for token in big_op.lower.iter().chain(&big_op.upper).flatten() {
+			token.visit(self);
+		}
+		visit_bound(self, big_op.variable.as_ref(), &big_op.body);
+	}
+	/// Default visits bounds, then body without uses of the bound variable
+	fn visit_integral(&mut self, integral: &Integral) {
+		for token in integral.lower.iter().chain(&integral.upper).flatten() {
+			token.visit(self);
+		}
+		visit_bound(self, Some(&integral.variable), &integral.body);
+	}
+	/// Default visits what the variable approaches,
+	/// then body without uses of the variable
+	fn visit_limit(&mut self, limit: &Limit) {
+		for token in &limit.approaching {
+			token.visit(self);
+		}
+		visit_bound(self, Some(&limit.variable), &limit.body);
+	}
+	/// Default visits every cell, row by row
+	fn visit_matrix(&mut self, matrix: &Matrix) {
+		for token in matrix.rows.iter().flatten().flatten() {
+			token.visit(self);
+		}
+	}
+	fn visit_text(&mut self, text: &str) {
+		_ = text;
+	}
+	fn visit_error(&mut self, span: Span) {
+		_ = span;
+	}
 	/// Default visits numerator and denominator
 	fn visit_frac(&mut self, frac: &Frac) {
 		for token in &frac.numerator {
@@ -105,33 +249,280 @@ pub trait TokenVisitor: Sized {
 			token.visit(self);
 		}
 	}
+	/// Default visits index (if any) and radicand
+	fn visit_root(&mut self, root: &Root) {
+		for token in root.index.iter().flatten() {
+			token.visit(self);
+		}
+		for token in &root.radicand {
+			token.visit(self);
+		}
+	}
+	/// Default visits subscript and power (if any), then argument
+	fn visit_function(&mut self, function: &Function) {
+		for token in function.subscript.iter().chain(&function.power).flatten() {
+			token.visit(self);
+		}
+		for token in &function.argument {
+			token.visit(self);
+		}
+	}
 }
 
 impl LatexToken {
 	pub fn visit<T>(&self, visitor: &mut T)
 	where
-		T: TokenVisitor,
+		T: TokenVisitor + ?Sized,
 	{
 		match self {
 			LatexToken::Neg => visitor.visit_neg(),
 			LatexToken::Num(num) => visitor.visit_num(num),
+			LatexToken::Decimal(decimal) => visitor.visit_decimal(decimal),
 			LatexToken::Mul => visitor.visit_mul(),
 			LatexToken::Add => visitor.visit_add(),
 			LatexToken::Exp(exp) => visitor.visit_exp(exp.as_ref()),
-			LatexToken::Eq => visitor.visit_eq(),
+			LatexToken::Relation(relation) => visitor.visit_relation(relation),
 			LatexToken::Ident(ident) => visitor.visit_ident(ident),
 			LatexToken::Bracketed(bracketed) => visitor.visit_bracketed(bracketed),
+			LatexToken::Abs(abs) => visitor.visit_abs(abs),
 			LatexToken::Frac(frac) => visitor.visit_frac(frac),
+			LatexToken::Root(root) => visitor.visit_root(root),
+			LatexToken::Function(function) => visitor.visit_function(function),
+			LatexToken::BigOp(big_op) => visitor.visit_big_op(big_op),
+			LatexToken::Integral(integral) => visitor.visit_integral(integral),
+			LatexToken::Limit(limit) => visitor.visit_limit(limit),
+			LatexToken::Matrix(matrix) => visitor.visit_matrix(matrix),
+			LatexToken::Text(text) => visitor.visit_text(text),
+			LatexToken::Error(span) => visitor.visit_error(*span),
 		}
 	}
 }
 
-/// A symbol
+impl LatexToken {
+	/// Applies `f` to every list of tokens directly inside this one,
+	/// e.g. both the numerator and denominator of a [`Frac`]
+	fn map_groups(
+		self,
+		f: &mut impl FnMut(Vec<Spanned<LatexToken>>) -> Vec<Spanned<LatexToken>>,
+	) -> LatexToken {
+		match self {
+			LatexToken::Exp(exp) => LatexToken::Exp(f(exp)),
+			LatexToken::Bracketed(Bracketed { bracket, inner }) => LatexToken::Bracketed(Bracketed {
+				bracket,
+				inner: f(inner),
+			}),
+			LatexToken::Abs(inner) => LatexToken::Abs(f(inner)),
+			LatexToken::Frac(Frac {
+				numerator,
+				denominator,
+			}) => LatexToken::Frac(Frac {
+				numerator: f(numerator),
+				denominator: f(denominator),
+			}),
+			LatexToken::Root(Root { index, radicand }) => LatexToken::Root(Root {
+				index: index.map(&mut *f),
+				radicand: f(radicand),
+			}),
+			LatexToken::Function(Function {
+				name,
+				subscript,
+				power,
+				argument,
+			}) => LatexToken::Function(Function {
+				name,
+				subscript: subscript.map(&mut *f),
+				power: power.map(&mut *f),
+				argument: f(argument),
+			}),
+			LatexToken::BigOp(BigOp {
+				kind,
+				variable,
+				lower,
+				upper,
+				body,
+			}) => LatexToken::BigOp(BigOp {
+				kind,
+				variable,
+				lower: lower.map(&mut *f),
+				upper: upper.map(&mut *f),
+				body: f(body),
+			}),
+			LatexToken::Integral(Integral {
+				lower,
+				upper,
+				body,
+				variable,
+			}) => LatexToken::Integral(Integral {
+				lower: lower.map(&mut *f),
+				upper: upper.map(&mut *f),
+				body: f(body),
+				variable,
+			}),
+			LatexToken::Limit(Limit {
+				variable,
+				approaching,
+				body,
+			}) => LatexToken::Limit(Limit {
+				variable,
+				approaching: f(approaching),
+				body: f(body),
+			}),
+			LatexToken::Matrix(Matrix { kind, rows }) => LatexToken::Matrix(Matrix {
+				kind,
+				rows: rows
+					.into_iter()
+					.map(|row| row.into_iter().map(&mut *f).collect())
+					.collect(),
+			}),
+			token @ (LatexToken::Num(_)
+			| LatexToken::Decimal(_)
+			| LatexToken::Neg
+			| LatexToken::Mul
+			| LatexToken::Add
+			| LatexToken::Relation(_)
+			| LatexToken::Ident(_)
+			| LatexToken::Text(_)
+			| LatexToken::Error(_)) => token,
+		}
+	}
+}
+
+/// Visits `body` with `visitor`, without uses of the bound `ident` however deeply nested.
+///
+/// Only tokens using `ident` are copied to take it out,
+/// and every method of `visitor` is still called on them
+fn visit_bound<V>(visitor: &mut V, ident: Option<&Ident>, body: &[Spanned<LatexToken>])
+where
+	V: TokenVisitor + ?Sized,
+{
+	for token in body {
+		match ident {
+			Some(ident) if uses(&token.value, ident) => {
+				if !matches!(&token.value, LatexToken::Ident(_)) {
+					without_ident(token.value.clone(), ident).visit(visitor);
+				}
+			}
+			_ => token.visit(visitor),
+		}
+	}
+}
+
+/// Whether `ident` is in `token` and not bound by something inside it
+fn uses(token: &LatexToken, ident: &Ident) -> bool {
+	struct Uses<'i> {
+		ident: &'i Ident,
+		found: bool,
+	}
+	impl TokenVisitor for Uses<'_> {
+		fn visit_ident(&mut self, ident: &Ident) {
+			self.found |= ident == self.ident;
+		}
+		fn visit_exp(&mut self, exp: &[Spanned<LatexToken>]) {
+			for token in exp {
+				token.visit(self);
+			}
+		}
+	}
+	let mut uses = Uses {
+		ident,
+		found: false,
+	};
+	token.visit(&mut uses);
+	uses.found
+}
+
+/// `token` without any uses of `ident`, however deeply nested
+fn without_ident(token: LatexToken, ident: &Ident) -> LatexToken {
+	fn remove(tokens: Vec<Spanned<LatexToken>>, ident: &Ident) -> Vec<Spanned<LatexToken>> {
+		tokens
+			.into_iter()
+			.filter(|token| !matches!(&token.value, LatexToken::Ident(other) if other == ident))
+			.map(|token| token.map(|token| without_ident(token, ident)))
+			.collect()
+	}
+	token.map_groups(&mut |group| remove(group, ident))
+}
+
+/// `=`, or an inequality like `<` or `\le`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum RelationKind {
+	Eq,
+	/// `\ne`
+	Ne,
+	/// `<`
+	Lt,
+	/// `\le`
+	Le,
+	/// `>`
+	Gt,
+	/// `\ge`
+	Ge,
+	/// `\approx`
+	Approx,
+}
+
+impl std::fmt::Display for RelationKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let symbol = match self {
+			RelationKind::Eq => "=",
+			RelationKind::Ne => "≠",
+			RelationKind::Lt => "<",
+			RelationKind::Le => "≤",
+			RelationKind::Gt => ">",
+			RelationKind::Ge => "≥",
+			RelationKind::Approx => "≈",
+		};
+		write!(f, "{}", symbol)
+	}
+}
+
+impl From<RelationKind> for LatexToken {
+	fn from(relation: RelationKind) -> Self {
+		LatexToken::Relation(relation)
+	}
+}
+
+/// An exact decimal number, `mantissa * 10^exponent`.
+///
+/// Trailing zeros are kept, so `0.50` is `50 * 10^-2`
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decimal {
+	#[cfg_attr(feature = "serde", serde(with = "json::biguint"))]
+	pub mantissa: BigUint,
+	pub exponent: i64,
+}
+
+impl From<Decimal> for LatexToken {
+	fn from(decimal: Decimal) -> Self {
+		LatexToken::Decimal(decimal)
+	}
+}
+
+/// A symbol
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+	feature = "serde",
+	serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
 pub enum Ident {
 	Tau,
 	Pi,
 	AlphabeticChar(char),
+	Greek(Greek),
+	/// `\operatorname{speed}`,
+	/// unless it's a [`FunctionName`]
+	Named(String),
+	/// e.g. `x_1` or `F_{net}`,
+	/// which are different variables to `x` and `F`
+	Subscripted {
+		base: Box<Ident>,
+		/// Only letters and digits
+		subscript: String,
+	},
 }
 
 impl std::fmt::Display for Ident {
@@ -140,6 +531,9 @@ impl std::fmt::Display for Ident {
 			Ident::Tau => write!(f, "τ"),
 			Ident::Pi => write!(f, "π"),
 			Ident::AlphabeticChar(char) => write!(f, "{}", char),
+			Ident::Greek(greek) => write!(f, "{}", greek),
+			Ident::Named(name) => write!(f, "{}", name),
+			Ident::Subscripted { base, subscript } => write!(f, "{}_{}", base, subscript),
 		}
 	}
 }
@@ -150,271 +544,1333 @@ impl From<Ident> for LatexToken {
 	}
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Bracket {
+	/// `\left(`, or a bare `(`
 	Round,
+	/// `\left[`
+	Square,
+	/// `\left\{`
+	Curly,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Frac {
-	pub numerator: Vec<LatexToken>,
-	pub denominator: Vec<LatexToken>,
+	pub numerator: Vec<Spanned<LatexToken>>,
+	pub denominator: Vec<Spanned<LatexToken>>,
 }
 
+/// `\sqrt{radicand}` or `\sqrt[index]{radicand}`
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Bracketed {
-	pub bracket: Bracket,
-	pub inner: Vec<LatexToken>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Root {
+	/// `None` for a plain square root
+	pub index: Option<Vec<Spanned<LatexToken>>>,
+	pub radicand: Vec<Spanned<LatexToken>>,
 }
 
-impl From<Bracketed> for LatexToken {
-	fn from(bracketed: Bracketed) -> Self {
-		LatexToken::Bracketed(bracketed)
+impl From<Root> for LatexToken {
+	fn from(root: Root) -> Self {
+		LatexToken::Root(root)
 	}
 }
 
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-	#[error("Parsing error: {0}")]
-	ParsingError(String),
+/// `\sin x`, `\sin\left(x\right)`, `\sin(x)`, `\log_{2} x` or `\sin^{2} x`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Function {
+	pub name: FunctionName,
+	/// e.g. the base of a logarithm
+	pub subscript: Option<Vec<Spanned<LatexToken>>>,
+	/// e.g. the `2` in `\sin^{2} x`, kept as written,
+	/// so `\sin^{-1}` isn't turned into `\arcsin`.
+	///
+	/// Defaults to `None` in JSON, which had no powers before
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub power: Option<Vec<Spanned<LatexToken>>>,
+	/// Brackets around the argument are not kept,
+	/// so `\sin x` and `\sin\left(x\right)` are the same
+	pub argument: Vec<Spanned<LatexToken>>,
 }
 
-pub type IResult<I, O, E = nom_language::error::VerboseError<I>> = Result<(I, O), nom::Err<E>>;
+impl From<Function> for LatexToken {
+	fn from(function: Function) -> Self {
+		LatexToken::Function(function)
+	}
+}
 
-impl Error {
-	fn handle_parsing_errors<T>(
-		res: Result<(&str, T), VerboseError<&str>>,
-		input: &str,
-	) -> Result<T, Error> {
-		match res {
-			Ok((leftover, tokens)) => {
-				assert_eq!(leftover.len(), 0);
-				Ok(tokens)
-			}
-			Err(err) => {
-				let pretty_trace = nom_language::error::convert_error(input, err);
-				Err(Error::ParsingError(pretty_trace))
-			}
-		}
+/// `\sum_{i=1}^{n} i^{2}` or `\prod_{k=1}^{n} k`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BigOp {
+	pub kind: BigOpKind,
+	/// The `i` in `\sum_{i=1}` or `\sum_i`
+	pub variable: Option<Ident>,
+	/// The `1` in `\sum_{i=1}`,
+	/// or the whole subscript if it doesn't bind a variable
+	pub lower: Option<Vec<Spanned<LatexToken>>>,
+	pub upper: Option<Vec<Spanned<LatexToken>>>,
+	/// Brackets around the body are not kept,
+	/// like [`Function::argument`]
+	pub body: Vec<Spanned<LatexToken>>,
+}
+
+impl From<BigOp> for LatexToken {
+	fn from(big_op: BigOp) -> Self {
+		LatexToken::BigOp(big_op)
 	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BigOpKind {
+	/// `\sum`
+	Sum,
+	/// `\prod`
+	Prod,
+}
 
-	#[cfg(test)]
-	fn assert_parsing_errors<T>(res: Result<(&str, T), VerboseError<&str>>, input: &str) -> T {
-		match Error::handle_parsing_errors(res, input) {
-			Ok(tokens) => tokens,
-			Err(err) => panic!("Parsing error: {}", err),
+impl BigOpKind {
+	/// Without the leading `\`
+	pub fn name(self) -> &'static str {
+		match self {
+			BigOpKind::Sum => "sum",
+			BigOpKind::Prod => "prod",
 		}
 	}
 }
 
-/// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and
-/// trailing whitespace, returning the output of `inner`.
-pub fn ws<'a, O, E: ParseError<&'a str>, F>(inner: F) -> impl Parser<&'a str, Output = O, Error = E>
-where
-	F: Parser<&'a str, Output = O, Error = E>,
-{
-	delimited(multispace0, inner, multispace0)
+/// `\int_{a}^{b} x^{2} dx`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Integral {
+	pub lower: Option<Vec<Spanned<LatexToken>>>,
+	pub upper: Option<Vec<Spanned<LatexToken>>>,
+	/// Everything up to the `dx`, may be empty
+	pub body: Vec<Spanned<LatexToken>>,
+	/// The `x` in `dx`
+	pub variable: Ident,
 }
 
-/// May leave whitespace or invalid content at the end
-fn tokens(input: &str) -> IResult<&str, Vec<LatexToken>> {
-	many1(alt((
-		neg, num, mul, add, exp, eq, pi, identifier, brackets, frac,
-	)))
-	.parse(input)
+impl From<Integral> for LatexToken {
+	fn from(integral: Integral) -> Self {
+		LatexToken::Integral(integral)
+	}
 }
 
-#[test]
-fn latex_tokens() {
-	let input = "123 x y z -5";
-	let tokens = Error::assert_parsing_errors(tokens(input).finish(), input);
-	assert_eq!(
-		tokens,
-		vec![
-			LatexToken::Num(BigUint::from(123u32)),
-			LatexToken::Ident(Ident::AlphabeticChar('x')),
-			LatexToken::Ident(Ident::AlphabeticChar('y')),
-			LatexToken::Ident(Ident::AlphabeticChar('z')),
-			LatexToken::Neg,
-			LatexToken::Num(BigUint::from(5u32))
-		]
-	);
+/// `\lim_{x \to a} body`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Limit {
+	pub variable: Ident,
+	pub approaching: Vec<Spanned<LatexToken>>,
+	/// Brackets around the body are not kept,
+	/// like [`Function::argument`]
+	pub body: Vec<Spanned<LatexToken>>,
 }
 
-fn neg(input: &str) -> IResult<&str, LatexToken> {
-	map(preceded(multispace0, tag("-")), |_str| LatexToken::Neg).parse(input)
+impl From<Limit> for LatexToken {
+	fn from(limit: Limit) -> Self {
+		LatexToken::Limit(limit)
+	}
 }
 
-fn add(input: &str) -> IResult<&str, LatexToken> {
-	map(preceded(multispace0, tag("+")), |_str| LatexToken::Add).parse(input)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FunctionName {
+	Sin,
+	Cos,
+	Tan,
+	Sec,
+	Csc,
+	Cot,
+	Arcsin,
+	Arccos,
+	Arctan,
+	Sinh,
+	Cosh,
+	Tanh,
+	Ln,
+	Log,
+	Exp,
 }
 
-/// ^ may have a corrosponding {}, or may not
-fn exp(input: &str) -> IResult<&str, LatexToken> {
-	alt((
-		map(
-			preceded(
-				multispace0,
-				preceded(tag("^"), delimited(ws(tag("{")), tokens, ws(tag("}")))),
-			),
-			LatexToken::Exp,
-		),
-		map(preceded(multispace0, tag("^")), |_str| {
-			LatexToken::Exp(vec![])
-		}),
-	))
-	.parse(input)
+impl FunctionName {
+	pub const ALL: [FunctionName; 15] = [
+		FunctionName::Sin,
+		FunctionName::Cos,
+		FunctionName::Tan,
+		FunctionName::Sec,
+		FunctionName::Csc,
+		FunctionName::Cot,
+		FunctionName::Arcsin,
+		FunctionName::Arccos,
+		FunctionName::Arctan,
+		FunctionName::Sinh,
+		FunctionName::Cosh,
+		FunctionName::Tanh,
+		FunctionName::Ln,
+		FunctionName::Log,
+		FunctionName::Exp,
+	];
+
+	/// Without the leading `\`
+	pub fn name(self) -> &'static str {
+		match self {
+			FunctionName::Sin => "sin",
+			FunctionName::Cos => "cos",
+			FunctionName::Tan => "tan",
+			FunctionName::Sec => "sec",
+			FunctionName::Csc => "csc",
+			FunctionName::Cot => "cot",
+			FunctionName::Arcsin => "arcsin",
+			FunctionName::Arccos => "arccos",
+			FunctionName::Arctan => "arctan",
+			FunctionName::Sinh => "sinh",
+			FunctionName::Cosh => "cosh",
+			FunctionName::Tanh => "tanh",
+			FunctionName::Ln => "ln",
+			FunctionName::Log => "log",
+			FunctionName::Exp => "exp",
+		}
+	}
 }
 
-/// spaces between digits don't matter, wow
-fn num(input: &str) -> IResult<&str, LatexToken> {
-	map(many1(preceded(multispace0, digit1)), |strs: Vec<&str>| {
-		let str = strs.join("");
-		LatexToken::Num(str.parse().expect("BigUint to parse from only digits"))
-	})
-	.parse(input)
+impl std::fmt::Display for FunctionName {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name())
+	}
 }
 
-#[test]
-fn latex_num() {
-	let bigint = {
-		// way bigger than u128 can hold!
-		let a = u128::MAX;
-		let b = u128::MAX / 2;
-		BigUint::from(a) + BigUint::from(b)
-	};
+/// `\begin{pmatrix} 1 & 2 \\ 3 & 4 \end{pmatrix}`,
+/// or a vector with only one column
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matrix {
+	pub kind: MatrixKind,
+	/// Every row has the same number of cells,
+	/// which may be empty
+	pub rows: Vec<Vec<Vec<Spanned<LatexToken>>>>,
+}
 
-	let (_remaining, t) = all_consuming(num)
-		.parse("  \t 510423550381407695195061911147652317182")
-		.unwrap();
-	assert_eq!(t, LatexToken::Num(bigint));
+impl Matrix {
+	/// `(rows, columns)`
+	pub fn shape(&self) -> (usize, usize) {
+		(self.rows.len(), self.rows.first().map_or(0, Vec::len))
+	}
 }
 
-fn mul(input: &str) -> IResult<&str, LatexToken> {
-	map(
-		preceded(multispace0, alt((tag(r#"\cdot"#), tag(r#"\ast"#)))),
-		|_str| LatexToken::Mul,
-	)
-	.parse(input)
+impl From<Matrix> for LatexToken {
+	fn from(matrix: Matrix) -> Self {
+		LatexToken::Matrix(matrix)
+	}
 }
 
-#[test]
-fn latex_mul() {
-	let (_remaining, t) = all_consuming(mul).parse(r"      \cdot").unwrap();
-	assert_eq!(t, LatexToken::Mul);
+/// Which brackets are around a [`Matrix`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MatrixKind {
+	/// `matrix`, without brackets
+	Plain,
+	/// `pmatrix`
+	Round,
+	/// `bmatrix`
+	Square,
+	/// `Bmatrix`
+	Curly,
+	/// `vmatrix`, e.g. for determinants
+	Bars,
+	/// `Vmatrix`
+	DoubleBars,
 }
 
-fn eq(input: &str) -> IResult<&str, LatexToken> {
-	map(preceded(multispace0, tag("=")), |_str| LatexToken::Eq).parse(input)
+impl MatrixKind {
+	pub const ALL: [MatrixKind; 6] = [
+		MatrixKind::Plain,
+		MatrixKind::Round,
+		MatrixKind::Square,
+		MatrixKind::Curly,
+		MatrixKind::Bars,
+		MatrixKind::DoubleBars,
+	];
+
+	pub fn begin(self) -> &'static str {
+		match self {
+			MatrixKind::Plain => r"\begin{matrix}",
+			MatrixKind::Round => r"\begin{pmatrix}",
+			MatrixKind::Square => r"\begin{bmatrix}",
+			MatrixKind::Curly => r"\begin{Bmatrix}",
+			MatrixKind::Bars => r"\begin{vmatrix}",
+			MatrixKind::DoubleBars => r"\begin{Vmatrix}",
+		}
+	}
+
+	pub fn end(self) -> &'static str {
+		match self {
+			MatrixKind::Plain => r"\end{matrix}",
+			MatrixKind::Round => r"\end{pmatrix}",
+			MatrixKind::Square => r"\end{bmatrix}",
+			MatrixKind::Curly => r"\end{Bmatrix}",
+			MatrixKind::Bars => r"\end{vmatrix}",
+			MatrixKind::DoubleBars => r"\end{Vmatrix}",
+		}
+	}
 }
 
-fn identifier(input: &str) -> IResult<&str, LatexToken> {
-	preceded(multispace0, alt((tau, pi, alphanumeric_ident))).parse(input)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bracketed {
+	pub bracket: Bracket,
+	pub inner: Vec<Spanned<LatexToken>>,
 }
 
-fn pi(input: &str) -> IResult<&str, LatexToken> {
-	map(preceded(multispace0, tag(r"\pi")), |_str| {
-		LatexToken::Ident(Ident::Pi)
-	})
-	.parse(input)
+impl From<Bracketed> for LatexToken {
+	fn from(bracketed: Bracketed) -> Self {
+		LatexToken::Bracketed(bracketed)
+	}
 }
 
-fn tau(input: &str) -> IResult<&str, LatexToken> {
-	map(preceded(multispace0, tag(r"\tau")), |_str| {
-		LatexToken::Ident(Ident::Tau)
-	})
-	.parse(input)
+/// What all the parsers take, which tracks the offset into the source latex
+/// and the [`State`] of the whole parse
+pub(crate) type Input<'i> = nom_locate::LocatedSpan<&'i str, &'i State>;
+
+/// Errors that [`parse_latex_lenient`] recovered from
+pub(crate) type Recovered = RefCell<Vec<ParsingError>>;
+
+/// Stops deeply nested latex like `((((…` from overflowing the stack
+const MAX_DEPTH: usize = 64;
+/// How much stack [`limited`] parsers can use before failing,
+/// half of a default thread's since unoptimized builds use a lot for each level
+const MAX_STACK: usize = 1024 * 1024;
+/// How many tokens can be tried for each byte of latex,
+/// so backtracking over latex like `||||…` can't take exponential time
+const STEPS_PER_BYTE: usize = 64;
+
+/// Shared by every parser for one parse
+#[derive(Debug)]
+pub(crate) struct State {
+	/// Only in [`parse_latex_lenient`] is there somewhere to put recovered errors
+	recovered: Option<Recovered>,
+	/// How many [`limited`] parsers are running
+	depth: Cell<usize>,
+	/// Where the stack was when the outermost [`limited`] parser started
+	stack_start: Cell<usize>,
+	/// How many more times a [`limited`] parser can run
+	steps: Cell<usize>,
 }
 
-/// This is a bit manual, is there a better way?
-fn alphanumeric_ident<'i, E>(input: &'i str) -> IResult<&'i str, LatexToken, E>
+impl State {
+	pub(crate) fn strict(latex: &str) -> Self {
+		State {
+			recovered: None,
+			depth: Cell::new(0),
+			stack_start: Cell::new(0),
+			steps: Cell::new(STEPS_PER_BYTE * (latex.len() + 16)),
+		}
+	}
+
+	fn lenient(latex: &str) -> Self {
+		State {
+			recovered: Some(RefCell::new(Vec::new())),
+			..State::strict(latex)
+		}
+	}
+}
+
+/// Runs `inner` one level deeper,
+/// failing instead if already [`MAX_DEPTH`] deep, using more than [`MAX_STACK`] or out of steps
+fn limited<'i, O, F>(mut inner: F) -> impl Parser<Input<'i>, Output = O, Error = NomError<'i>>
 where
-	E: ParseError<&'i str>,
+	F: Parser<Input<'i>, Output = O, Error = NomError<'i>>,
 {
-	let char = input
-		.chars()
-		.next()
-		.ok_or(E::from_error_kind(input, nom::error::ErrorKind::Char))
-		.map_err(|err| nom::Err::Error(err))?;
-	if char.is_alphabetic() {
-		Ok((&input[1..], LatexToken::Ident(Ident::AlphabeticChar(char))))
-	} else {
-		Err(nom::Err::Error(E::from_error_kind(
+	move |input: Input<'i>| {
+		let state = input.extra;
+		let (depth, steps) = (state.depth.get(), state.steps.get());
+		// the address of a local is roughly where the stack is
+		let stack = std::hint::black_box(&depth) as *const usize as usize;
+		if depth == 0 {
+			state.stack_start.set(stack);
+		}
+		let expected = if depth >= MAX_DEPTH || state.stack_start.get().abs_diff(stack) > MAX_STACK {
+			"less deeply nested latex"
+		} else if steps == 0 {
+			"simpler latex"
+		} else {
+			state.depth.set(depth + 1);
+			state.steps.set(steps - 1);
+			let res = inner.parse(input);
+			state.depth.set(depth);
+			return res;
+		};
+		Err(nom::Err::Failure(NomError::expected(
 			input,
-			nom::error::ErrorKind::Char,
+			Expected::Description(expected),
 		)))
 	}
 }
 
-#[test]
-fn latex_identifiers() {
-	let input = r"   x y   z\pi";
-	let tokens = Error::assert_parsing_errors(many1(identifier).parse(input).finish(), input);
-	assert_eq!(
-		tokens,
-		vec![
-			Ident::AlphabeticChar('x').into(),
-			Ident::AlphabeticChar('y').into(),
-			Ident::AlphabeticChar('z').into(),
-			Ident::Pi.into()
-		]
-	);
-}
+type IResult<'i, O> = Result<(Input<'i>, O), nom::Err<NomError<'i>>>;
 
-fn brackets(input: &str) -> IResult<&str, LatexToken> {
-	delimited(ws(tag(r"\left(")), tokens, ws(tag(r"\right)")))
-		.map(|tokens| {
-			LatexToken::Bracketed(Bracketed {
-				bracket: Bracket::Round,
-				inner: tokens,
-			})
-		})
-		.parse(input)
+/// Also errors if anything is left over
+fn handle_parsing_errors<T>(res: IResult<'_, T>) -> Result<T, Error> {
+	handle_parsing_errors_with(res, token)
 }
 
-#[test]
-fn latex_brackets() {
-	let input = r"\left( 5 \cdot 7 \right)";
-	let tokens = Error::assert_parsing_errors(tokens.parse(input).finish(), input);
-	assert_eq!(
-		tokens,
-		vec![
-			Bracketed {
-				bracket: Bracket::Round,
-				inner: vec![
-					LatexToken::Num(5u32.into()),
-					LatexToken::Mul,
-					LatexToken::Num(7u32.into())
-				]
+/// Like [`handle_parsing_errors`],
+/// but `explain` is what's tried on anything left over to explain why it couldn't be parsed
+fn handle_parsing_errors_with<'i, T, O>(
+	res: IResult<'i, T>,
+	mut explain: impl Parser<Input<'i>, Output = O, Error = NomError<'i>>,
+) -> Result<T, Error> {
+	let err = match res {
+		Ok((leftover, tokens)) if leftover.is_empty() => return Ok(tokens),
+		Ok((leftover, _tokens)) => match explain.parse(leftover) {
+			Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+				err.or(NomError::expected(leftover, Expected::EndOfInput))
 			}
-			.into()
-		]
-	);
+			_ => NomError::expected(leftover, Expected::EndOfInput),
+		},
+		Err(nom::Err::Error(err) | nom::Err::Failure(err)) => err,
+		Err(nom::Err::Incomplete(_)) => unreachable!("Only complete parsers are used"),
+	};
+	Err(ParsingError::from(err).into())
 }
 
-/// Will error on content in numerator or denominator
-/// failing to parse
-fn frac(input: &str) -> IResult<&str, LatexToken> {
-	map(
-		(
-			ws(tag(r"\frac")),
-			ws(delimited(tag("{"), ws(tokens), tag("}"))),
-			ws(delimited(tag("{"), ws(tokens), tag("}"))),
-		),
-		|(_frac, numerator, denominator)| {
-			LatexToken::Frac(Frac {
-				numerator,
-				denominator,
-			})
-		},
-	)
-	.parse(input)
+#[cfg(test)]
+fn assert_parsing_errors<T>(res: IResult<'_, T>) -> T {
+	match handle_parsing_errors(res) {
+		Ok(tokens) => tokens,
+		Err(err) => panic!("Parsing error: {}", err),
+	}
 }
 
-#[test]
+/// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and
+/// trailing whitespace, returning the output of `inner`.
+pub fn ws<I, O, E, F>(inner: F) -> impl Parser<I, Output = O, Error = E>
+where
+	I: nom::Input,
+	<I as nom::Input>::Item: nom::AsChar,
+	E: ParseError<I>,
+	F: Parser<I, Output = O, Error = E>,
+{
+	delimited(multispace0, inner, multispace0)
+}
+
+/// Like [`nom::bytes::complete::tag`],
+/// but remembers what was expected for error messages
+fn tag<'i>(
+	literal: &'static str,
+) -> impl Parser<Input<'i>, Output = Input<'i>, Error = NomError<'i>> {
+	move |input: Input<'i>| {
+		if input.starts_with(literal) {
+			Ok(nom::Input::take_split(&input, literal.len()))
+		} else {
+			Err(nom::Err::Error(NomError::expected(
+				input,
+				Expected::Literal(literal),
+			)))
+		}
+	}
+}
+
+/// Records the [`Span`] `inner` consumed,
+/// not including trailing whitespace
+fn spanned<'i, O, F>(
+	mut inner: F,
+) -> impl Parser<Input<'i>, Output = Spanned<O>, Error = NomError<'i>>
+where
+	F: Parser<Input<'i>, Output = O, Error = NomError<'i>>,
+{
+	move |input: Input<'i>| {
+		let (rest, value) = inner.parse(input)?;
+		let start = input.location_offset();
+		let consumed = &input[..rest.location_offset() - start];
+		let span = Span::new(start, start + consumed.trim_end().len());
+		Ok((rest, Spanned::new(value, span)))
+	}
+}
+
+/// May leave whitespace or invalid content at the end.
+///
+/// When lenient never fails,
+/// but outputs a [`LatexToken::Error`] if there are no tokens
+fn tokens(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	let Some(recovered) = &input.extra.recovered else {
+		return many1(preceded(multispace0, spanned(token))).parse(input);
+	};
+	let (rest, mut tokens) = lenient_tokens(input, recovered);
+	if tokens.is_empty() {
+		let span = Span::new(rest.location_offset(), rest.location_offset());
+		let err = NomError::expected(rest, Expected::Description("an expression"));
+		recovered.borrow_mut().push(err.into());
+		push_error(&mut tokens, span);
+	}
+	Ok((rest, tokens))
+}
+
+/// Like [`tokens`], but fails if there are none even when lenient
+fn some_tokens(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	let Some(recovered) = &input.extra.recovered else {
+		return tokens(input);
+	};
+	match lenient_tokens(input, recovered) {
+		(_rest, tokens) if tokens.is_empty() => Err(nom::Err::Error(NomError::expected(
+			input,
+			Expected::Description("an expression"),
+		))),
+		(rest, tokens) => Ok((rest, tokens)),
+	}
+}
+
+/// Skips over anything that isn't a token,
+/// until the end or a closing bracket that isn't part of a token
+fn lenient_tokens<'i>(
+	mut input: Input<'i>,
+	recovered: &Recovered,
+) -> (Input<'i>, Vec<Spanned<LatexToken>>) {
+	let mut tokens = Vec::new();
+	loop {
+		let whitespace = input.len() - input.trim_start().len();
+		input = nom::Input::take_split(&input, whitespace).0;
+		if input.is_empty() {
+			break;
+		}
+		let before = recovered.borrow().len();
+		match spanned(token).parse(input) {
+			Ok((rest, token)) => {
+				tokens.push(token);
+				input = rest;
+			}
+			Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+				// recovered from inside alternatives that failed anyway
+				recovered.borrow_mut().truncate(before);
+				let closing = ["}", "]", ")", "|", r"\right", "&", r"\\", r"\end"];
+				if closing.iter().any(|closing| input.starts_with(closing)) {
+					break;
+				}
+				let (rest, span) = skip_invalid(input);
+				if push_error(&mut tokens, span) {
+					recovered.borrow_mut().push(err.into());
+				}
+				input = rest;
+			}
+			Err(nom::Err::Incomplete(_)) => unreachable!("Only complete parsers are used"),
+		}
+	}
+	(input, tokens)
+}
+
+#[test]
+fn latex_limits() {
+	// on the test thread's stack, the same size as a default thread's
+	let nested = format!("{}x{}", "(".repeat(8), ")".repeat(8));
+	assert!(parse_latex(&nested).is_ok());
+	for nested in [
+		"(".repeat(1_000),
+		r"\left(".repeat(1_000),
+		r"\frac{".repeat(1_000),
+		r"\sqrt{".repeat(1_000),
+		r"x^{".repeat(1_000),
+		r"\sum_{".repeat(1_000),
+		r"\begin{pmatrix}".repeat(1_000),
+	] {
+		let Err(Error::ParsingError(err)) = parse_latex(&nested) else {
+			panic!("Expected deep nesting to error");
+		};
+		assert!(
+			err
+				.expected
+				.contains(&Expected::Description("less deeply nested latex"))
+		);
+	}
+	let (_tokens, errors) = parse_latex_lenient(&r"\frac{".repeat(1_000));
+	assert!(!errors.is_empty());
+	let (_tokens, errors) = parse_latex_lenient(&r"\sin".repeat(1_000));
+	assert!(!errors.is_empty());
+	let (_tokens, errors) = parse_latex_lenient(&r"\sin(".repeat(1_000));
+	assert!(!errors.is_empty());
+
+	// exponential without a limit on steps
+	let (_tokens, errors) = parse_latex_lenient(&"|".repeat(101));
+	assert!(!errors.is_empty());
+	assert!(asciimath::parse_asciimath(&"sqrt".repeat(1_000)).is_err());
+	assert!(asciimath::parse_asciimath(&"(".repeat(1_000)).is_err());
+	assert!(asciimath::parse_asciimath(&"root(".repeat(1_000)).is_err());
+}
+
+/// A whole command like `\foo`, otherwise one character
+fn skip_invalid(input: Input) -> (Input, Span) {
+	let len = match input.strip_prefix('\\') {
+		Some(command) => {
+			let name_len = command
+				.find(|char: char| !char.is_alphabetic())
+				.unwrap_or(command.len());
+			let name_len = match name_len {
+				// e.g. `\{`
+				0 => command.chars().next().map(char::len_utf8).unwrap_or(0),
+				name_len => name_len,
+			};
+			1 + name_len
+		}
+		None => input.chars().next().map(char::len_utf8).unwrap_or(0),
+	};
+	let start = input.location_offset();
+	let (rest, _skipped) = nom::Input::take_split(&input, len);
+	(rest, Span::new(start, start + len))
+}
+
+/// Extends the last token instead if it's also an error,
+/// returning whether a new error was pushed
+fn push_error(tokens: &mut Vec<Spanned<LatexToken>>, span: Span) -> bool {
+	if let Some(Spanned {
+		span: last,
+		value: LatexToken::Error(error),
+	}) = tokens.last_mut()
+	{
+		*last = last.join(span);
+		*error = *last;
+		return false;
+	}
+	tokens.push(Spanned::new(LatexToken::Error(span), span));
+	true
+}
+
+#[test]
+fn latex_lenient() {
+	let error =
+		|start, end| -> Spanned<LatexToken> { LatexToken::Error(Span::new(start, end)).into() };
+	let num = |num: u32| -> Spanned<LatexToken> { LatexToken::Num(num.into()).into() };
+
+	let (parsed, errors) = parse_latex_lenient(r"\frac{2}{}");
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::Frac(Frac {
+				numerator: vec![num(2)],
+				denominator: vec![error(9, 9)],
+			})
+			.into()
+		]
+	);
+	assert_eq!(errors.len(), 1);
+	assert_eq!(errors[0].offset, 9);
+	assert_eq!(
+		errors[0].expected,
+		vec![Expected::Description("an expression")]
+	);
+
+	let (parsed, errors) = parse_latex_lenient(r"2 + \foo\bar 3 } 4");
+	assert_eq!(
+		parsed,
+		vec![
+			num(2),
+			LatexToken::Add.into(),
+			error(4, 12),
+			num(3),
+			error(15, 16),
+			num(4)
+		]
+	);
+	let offsets: Vec<usize> = errors.iter().map(|err| err.offset).collect();
+	assert_eq!(offsets, vec![4, 15]);
+	assert_eq!(errors[1].expected, vec![Expected::EndOfInput]);
+
+	let (parsed, errors) = parse_latex_lenient(r"\left( x + \sqrt");
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::Bracketed(Bracketed {
+				bracket: Bracket::Round,
+				inner: vec![
+					LatexToken::Ident(Ident::AlphabeticChar('x')).into(),
+					LatexToken::Add.into(),
+					LatexToken::Root(Root {
+						index: None,
+						radicand: vec![error(16, 16)],
+					})
+					.into(),
+				],
+			})
+			.into()
+		]
+	);
+	assert_eq!(errors.len(), 2);
+
+	// the same as strict parsing when there are no errors
+	for latex in [
+		r"x^",
+		r"|x| + \left|-2\right|",
+		r"\sin(x) \le \sqrt[3]{\frac{1}{2}}",
+	] {
+		let (parsed, errors) = parse_latex_lenient(latex);
+		assert_eq!(errors, vec![]);
+		assert_eq!(parsed, parse_latex(latex).unwrap());
+	}
+	assert_eq!(parse_latex_lenient("  "), (vec![], vec![]));
+}
+
+/// When lenient, if `inner` fails then records the error
+/// and outputs `placeholder` instead, without consuming anything
+fn recover<'i, O, F>(
+	mut inner: F,
+	placeholder: impl Fn(Input<'i>) -> O,
+) -> impl Parser<Input<'i>, Output = O, Error = NomError<'i>>
+where
+	F: Parser<Input<'i>, Output = O, Error = NomError<'i>>,
+{
+	move |input: Input<'i>| match (inner.parse(input), &input.extra.recovered) {
+		(Err(nom::Err::Error(err) | nom::Err::Failure(err)), Some(recovered)) => {
+			recovered.borrow_mut().push(err.into());
+			Ok((input, placeholder(input)))
+		}
+		(res, _) => res,
+	}
+}
+
+/// A closing bracket, which is assumed to be there when lenient
+fn closing<'i>(
+	literal: &'static str,
+) -> impl Parser<Input<'i>, Output = Input<'i>, Error = NomError<'i>> {
+	recover(ws(tag(literal)), |input| input)
+}
+
+/// A [`group`] that can't be left out,
+/// so when lenient is a [`LatexToken::Error`] if missing
+fn required_group<'i>(
+	name: &'static str,
+) -> impl Parser<Input<'i>, Output = Vec<Spanned<LatexToken>>, Error = NomError<'i>> {
+	recover(ws(group(name)), |input| {
+		let span = Span::new(input.location_offset(), input.location_offset());
+		vec![Spanned::new(LatexToken::Error(span), span)]
+	})
+}
+
+fn token(input: Input) -> IResult<LatexToken> {
+	limited(alt((
+		neg, num, mul, add, exp, relation, identifier, brackets, abs, frac, root, function, big_op,
+		integral, limit, matrix, text,
+	)))
+	.parse(input)
+}
+
+/// `{ ... }`
+fn group<'i>(
+	name: &'static str,
+) -> impl Parser<Input<'i>, Output = Vec<Spanned<LatexToken>>, Error = NomError<'i>> {
+	context(name, delimited(ws(tag("{")), tokens, closing("}")))
+}
+
+#[test]
+fn latex_tokens() {
+	let input = "123 x y z -5";
+	let tokens = assert_parsing_errors(tokens(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		tokens,
+		vec![
+			LatexToken::Num(BigUint::from(123u32)).into(),
+			LatexToken::Ident(Ident::AlphabeticChar('x')).into(),
+			LatexToken::Ident(Ident::AlphabeticChar('y')).into(),
+			LatexToken::Ident(Ident::AlphabeticChar('z')).into(),
+			LatexToken::Neg.into(),
+			LatexToken::Num(BigUint::from(5u32)).into()
+		]
+	);
+	let spans: Vec<Span> = tokens.iter().map(|token| token.span).collect();
+	assert_eq!(
+		spans,
+		vec![
+			Span::new(0, 3),
+			Span::new(4, 5),
+			Span::new(6, 7),
+			Span::new(8, 9),
+			Span::new(10, 11),
+			Span::new(11, 12)
+		]
+	);
+}
+
+#[test]
+fn latex_parsing_error() {
+	let Err(Error::ParsingError(err)) = parse_latex(r"\frac{2}{}") else {
+		panic!("Expected a parsing error");
+	};
+	assert_eq!(err.offset, 9);
+	assert_eq!(err.found, Some('}'));
+	assert!(err.expected.contains(&Expected::Description("a number")));
+	assert_eq!(err.context, vec!["denominator", "fraction"]);
+
+	let Err(Error::ParsingError(err)) = parse_latex(r"2 \right)") else {
+		panic!("Expected a parsing error");
+	};
+	assert_eq!(err.span(), Span::new(2, 3));
+	assert!(err.expected.contains(&Expected::EndOfInput));
+}
+
+fn neg(input: Input) -> IResult<LatexToken> {
+	map(preceded(multispace0, tag("-")), |_str| LatexToken::Neg).parse(input)
+}
+
+fn add(input: Input) -> IResult<LatexToken> {
+	map(preceded(multispace0, tag("+")), |_str| LatexToken::Add).parse(input)
+}
+
+/// ^ may have a corrosponding {}, or may not
+fn exp(input: Input) -> IResult<LatexToken> {
+	alt((
+		map(
+			preceded(multispace0, preceded(tag("^"), group("exponent"))),
+			LatexToken::Exp,
+		),
+		map(preceded(multispace0, tag("^")), |_str| {
+			LatexToken::Exp(vec![])
+		}),
+	))
+	.parse(input)
+}
+
+/// spaces between digits don't matter, wow
+fn digits(input: Input) -> IResult<String> {
+	map(many1(preceded(multispace0, digit1)), |strs: Vec<Input>| {
+		strs.iter().map(|str| *str.fragment()).collect()
+	})
+	.parse(input)
+}
+
+/// Decimals with a bigger power of ten than this fail to parse,
+/// so nothing after parsing has to handle a huge exponent
+pub(crate) const MAX_DECIMAL_EXPONENT: i64 = 10_000;
+
+/// Integers, decimals like `3.14`,
+/// and scientific notation like `6.02\times10^{23}`
+fn num(input: Input) -> IResult<LatexToken> {
+	let (rest, (whole, fraction, scientific)) = (
+		digits,
+		opt(preceded(ws(tag(".")), digits)),
+		opt(scientific_exponent),
+	)
+		.parse(input)?;
+	let fraction = fraction.unwrap_or_default();
+	let mantissa = format!("{}{}", whole, fraction)
+		.parse()
+		.expect("BigUint to parse from only digits");
+	if fraction.is_empty() && scientific.is_none() {
+		return Ok((rest, LatexToken::Num(mantissa)));
+	}
+	let exponent = i64::try_from(fraction.len())
+		.ok()
+		.and_then(|places| scientific.unwrap_or(0).checked_sub(places))
+		.filter(|exponent| exponent.abs() <= MAX_DECIMAL_EXPONENT);
+	match exponent {
+		Some(exponent) => Ok((rest, LatexToken::Decimal(Decimal { mantissa, exponent }))),
+		None => Err(nom::Err::Failure(NomError::expected(
+			input,
+			Expected::Description("a smaller exponent"),
+		))),
+	}
+}
+
+/// The `\times10^{23}` in `6.02\times10^{23}`,
+/// fails if the exponent isn't an integer so it can be parsed as multiplication instead
+fn scientific_exponent(input: Input) -> IResult<i64> {
+	let signed_int = |input| {
+		map(
+			(opt(ws(tag("-"))), digit1),
+			|(neg, digits): (Option<Input>, Input)| {
+				let exponent = digits.parse::<i64>().ok()?;
+				Some(if neg.is_some() { -exponent } else { exponent })
+			},
+		)
+		.parse(input)
+	};
+	let (rest, exponent) = preceded(
+		(ws(tag(r"\times")), tag("10"), ws(tag("^"))),
+		alt((
+			delimited(ws(tag("{")), signed_int, ws(tag("}"))),
+			map(satisfy(|char| char.is_ascii_digit()), |digit| {
+				Some(digit as i64 - '0' as i64)
+			}),
+		)),
+	)
+	.parse(input)?;
+	match exponent {
+		Some(exponent) => Ok((rest, exponent)),
+		None => Err(nom::Err::Failure(NomError::expected(
+			input,
+			Expected::Description("a smaller exponent"),
+		))),
+	}
+}
+
+#[test]
+fn latex_num() {
+	let bigint = {
+		// way bigger than u128 can hold!
+		let a = u128::MAX;
+		let b = u128::MAX / 2;
+		BigUint::from(a) + BigUint::from(b)
+	};
+
+	let input = "  \t 510423550381407695195061911147652317182";
+	let t = assert_parsing_errors(num(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(t, LatexToken::Num(bigint));
+}
+
+#[test]
+fn latex_decimal() {
+	let input = r"3.14 + 0.50 - 6.02\times10^{23} 1.5 \times 10^{-3} \cdot 2\times10^2 \times x";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::Decimal(Decimal {
+				mantissa: 314u32.into(),
+				exponent: -2
+			})
+			.into(),
+			LatexToken::Add.into(),
+			LatexToken::Decimal(Decimal {
+				mantissa: 50u32.into(),
+				exponent: -2
+			})
+			.into(),
+			LatexToken::Neg.into(),
+			LatexToken::Decimal(Decimal {
+				mantissa: 602u32.into(),
+				exponent: 21
+			})
+			.into(),
+			LatexToken::Decimal(Decimal {
+				mantissa: 15u32.into(),
+				exponent: -4
+			})
+			.into(),
+			LatexToken::Mul.into(),
+			LatexToken::Decimal(Decimal {
+				mantissa: 2u32.into(),
+				exponent: 2
+			})
+			.into(),
+			LatexToken::Mul.into(),
+			LatexToken::Ident(Ident::AlphabeticChar('x')).into(),
+		]
+	);
+}
+
+#[test]
+fn latex_decimal_exponent_bound() {
+	for input in [
+		r"1.55\times10^{-9223372036854775807}",
+		r"1\times10^{-99999999999}",
+		r"2\times10^{10001}",
+		r"2\times10^{99999999999999999999}",
+	] {
+		let Err(Error::ParsingError(err)) = parse_latex(input) else {
+			panic!("Expected {} to fail to parse", input);
+		};
+		assert!(
+			err
+				.expected
+				.contains(&Expected::Description("a smaller exponent")),
+			"{}",
+			input
+		);
+	}
+	assert!(parse_latex(&format!("0.{}", "1".repeat(10_001))).is_err());
+	assert!(parse_latex(r"1.5\times10^{-9998}").is_ok());
+}
+
+fn mul(input: Input) -> IResult<LatexToken> {
+	map(
+		preceded(
+			multispace0,
+			alt((tag(r#"\cdot"#), tag(r#"\ast"#), tag(r#"\times"#))),
+		),
+		|_str| LatexToken::Mul,
+	)
+	.parse(input)
+}
+
+#[test]
+fn latex_mul() {
+	let t = assert_parsing_errors(mul(Input::new_extra(r"      \cdot", &State::strict(""))));
+	assert_eq!(t, LatexToken::Mul);
+}
+
+fn relation(input: Input) -> IResult<LatexToken> {
+	let symbol = |symbol, relation| map(tag(symbol), move |_str| relation);
+	let command = |input| {
+		command(
+			input,
+			[
+				("ne", RelationKind::Ne),
+				("neq", RelationKind::Ne),
+				("lt", RelationKind::Lt),
+				("le", RelationKind::Le),
+				("leq", RelationKind::Le),
+				("gt", RelationKind::Gt),
+				("ge", RelationKind::Ge),
+				("geq", RelationKind::Ge),
+				("approx", RelationKind::Approx),
+			],
+			r"a relation like \le",
+		)
+	};
+	map(
+		preceded(
+			multispace0,
+			alt((
+				symbol("=", RelationKind::Eq),
+				symbol("<", RelationKind::Lt),
+				symbol(">", RelationKind::Gt),
+				command,
+			)),
+		),
+		LatexToken::Relation,
+	)
+	.parse(input)
+}
+
+#[test]
+fn latex_relation() {
+	let input = r"2x+1 < 5 \le y \ne \left( 3 \right) \geq 1 \approx 0.9 = 1";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	let relations: Vec<RelationKind> = parsed
+		.iter()
+		.filter_map(|token| match token.value {
+			LatexToken::Relation(relation) => Some(relation),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(
+		relations,
+		vec![
+			RelationKind::Lt,
+			RelationKind::Le,
+			RelationKind::Ne,
+			RelationKind::Ge,
+			RelationKind::Approx,
+			RelationKind::Eq,
+		]
+	);
+}
+
+fn identifier(input: Input) -> IResult<LatexToken> {
+	map(
+		(
+			preceded(
+				multispace0,
+				alt((tau, pi, greek, named_ident, alphanumeric_ident)),
+			),
+			opt(preceded(ws(tag("_")), ident_subscript)),
+		),
+		|(ident, subscript)| match (ident, subscript) {
+			(LatexToken::Ident(base), Some(subscript)) => LatexToken::Ident(Ident::Subscripted {
+				base: Box::new(base),
+				subscript,
+			}),
+			(ident, _) => ident,
+		},
+	)
+	.parse(input)
+}
+
+/// The `speed` in `\operatorname{speed}`
+fn operatorname(input: Input) -> IResult<String> {
+	preceded(
+		(tag(r"\operatorname"), ws(tag("{"))),
+		terminated(
+			map(alphanumeric1, |name: Input| name.to_string()),
+			closing("}"),
+		),
+	)
+	.parse(input)
+}
+
+fn named_ident(input: Input) -> IResult<LatexToken> {
+	let (rest, name) = operatorname(input)?;
+	if FunctionName::ALL
+		.iter()
+		.any(|function| function.name() == name)
+	{
+		// parsed by `function` instead
+		return Err(nom::Err::Error(NomError::expected(
+			input,
+			Expected::Description("a name"),
+		)));
+	}
+	Ok((rest, LatexToken::Ident(Ident::Named(name))))
+}
+
+/// `1` or `{net}`
+fn ident_subscript(input: Input) -> IResult<String> {
+	alt((
+		delimited(
+			ws(tag("{")),
+			map(alphanumeric1, |subscript: Input| subscript.to_string()),
+			ws(tag("}")),
+		),
+		map(satisfy(char::is_alphanumeric), String::from),
+	))
+	.parse(input)
+}
+
+fn greek(input: Input) -> IResult<LatexToken> {
+	map(
+		|input| {
+			command(
+				input,
+				Greek::all().map(|greek| (greek.name(), greek)),
+				r"a greek letter like \alpha",
+			)
+		},
+		|greek| LatexToken::Ident(Ident::Greek(greek)),
+	)
+	.parse(input)
+}
+
+/// Matches the control sequence `\{name}` for one of `commands`,
+/// but not as the start of a longer one, e.g. `\sin` won't match `\sinh`
+fn command<'i, T>(
+	input: Input<'i>,
+	commands: impl IntoIterator<Item = (impl AsRef<str>, T)>,
+	expected: &'static str,
+) -> IResult<'i, T> {
+	if let Some(after_slash) = input.strip_prefix('\\') {
+		for (name, value) in commands {
+			let name = name.as_ref();
+			if let Some(rest) = after_slash.strip_prefix(name)
+				&& !rest.starts_with(char::is_alphabetic)
+			{
+				let (rest, _command) = nom::Input::take_split(&input, 1 + name.len());
+				return Ok((rest, value));
+			}
+		}
+	}
+	Err(nom::Err::Error(NomError::expected(
+		input,
+		Expected::Description(expected),
+	)))
+}
+
+fn pi(input: Input) -> IResult<LatexToken> {
+	map(preceded(multispace0, tag(r"\pi")), |_str| {
+		LatexToken::Ident(Ident::Pi)
+	})
+	.parse(input)
+}
+
+fn tau(input: Input) -> IResult<LatexToken> {
+	map(preceded(multispace0, tag(r"\tau")), |_str| {
+		LatexToken::Ident(Ident::Tau)
+	})
+	.parse(input)
+}
+
+/// This is a bit manual, is there a better way?
+fn alphanumeric_ident(input: Input) -> IResult<LatexToken> {
+	match input.chars().next() {
+		Some(char) if char.is_alphabetic() => {
+			let (rest, _char) = nom::Input::take_split(&input, char.len_utf8());
+			Ok((rest, LatexToken::Ident(Ident::AlphabeticChar(char))))
+		}
+		_ => Err(nom::Err::Error(NomError::expected(
+			input,
+			Expected::Description("a letter"),
+		))),
+	}
+}
+
+#[test]
+fn latex_identifiers() {
+	let input = r"   x y   z\pi";
+	let state = State::strict(input);
+	let tokens = assert_parsing_errors(many1(identifier).parse(Input::new_extra(input, &state)));
+	assert_eq!(
+		tokens,
+		vec![
+			Ident::AlphabeticChar('x').into(),
+			Ident::AlphabeticChar('y').into(),
+			Ident::AlphabeticChar('z').into(),
+			Ident::Pi.into()
+		]
+	);
+}
+
+#[test]
+fn latex_greek_and_subscripts() {
+	let input = r"\alpha \Omega x_1 v_{0} F_{net} \pi_2";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	let idents: Vec<Ident> = parsed
+		.into_iter()
+		.map(|token| match token.value {
+			LatexToken::Ident(ident) => ident,
+			token => panic!("Expected an ident, found {:?}", token),
+		})
+		.collect();
+	let subscripted = |base, subscript: &str| Ident::Subscripted {
+		base: Box::new(base),
+		subscript: subscript.to_owned(),
+	};
+	assert_eq!(
+		idents,
+		vec![
+			Ident::Greek(Greek::new(GreekLetter::Alpha, false).unwrap()),
+			Ident::Greek(Greek::new(GreekLetter::Omega, true).unwrap()),
+			subscripted(Ident::AlphabeticChar('x'), "1"),
+			subscripted(Ident::AlphabeticChar('v'), "0"),
+			subscripted(Ident::AlphabeticChar('F'), "net"),
+			subscripted(Ident::Pi, "2"),
+		]
+	);
+	assert_eq!(idents[1].to_string(), "Ω");
+	assert_eq!(idents[4].to_string(), "F_net");
+
+	// not a prefix of a longer command
+	assert!(parse_latex(r"\etaa").is_err());
+}
+
+fn brackets(input: Input) -> IResult<LatexToken> {
+	let left_right = |bracket: Bracket| {
+		let (left, right) = match bracket {
+			Bracket::Round => (r"\left(", r"\right)"),
+			Bracket::Square => (r"\left[", r"\right]"),
+			Bracket::Curly => (r"\left\{", r"\right\}"),
+		};
+		map(
+			delimited(ws(tag(left)), tokens, closing(right)),
+			move |inner| Bracketed { bracket, inner },
+		)
+	};
+	let bare = map(delimited(ws(tag("(")), tokens, closing(")")), |inner| {
+		Bracketed {
+			bracket: Bracket::Round,
+			inner,
+		}
+	});
+	context(
+		"brackets",
+		alt((
+			left_right(Bracket::Round),
+			left_right(Bracket::Square),
+			left_right(Bracket::Curly),
+			bare,
+		)),
+	)
+	.map(LatexToken::Bracketed)
+	.parse(input)
+}
+
+#[test]
+fn latex_brackets() {
+	let input = r"\left( 5 \cdot 7 \right)";
+	let tokens = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		tokens,
+		vec![
+			LatexToken::Bracketed(Bracketed {
+				bracket: Bracket::Round,
+				inner: vec![
+					LatexToken::Num(5u32.into()).into(),
+					LatexToken::Mul.into(),
+					LatexToken::Num(7u32.into()).into()
+				]
+			})
+			.into()
+		]
+	);
+	assert_eq!(tokens[0].span, Span::new(0, input.len()));
+}
+
+#[test]
+fn latex_bracket_kinds() {
+	let input = r"\left[ 1 \right] \left\{ 2 \right\} (3)";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	let bracketed = |bracket, num: u32| -> Spanned<LatexToken> {
+		LatexToken::Bracketed(Bracketed {
+			bracket,
+			inner: vec![LatexToken::Num(num.into()).into()],
+		})
+		.into()
+	};
+	assert_eq!(
+		parsed,
+		vec![
+			bracketed(Bracket::Square, 1),
+			bracketed(Bracket::Curly, 2),
+			bracketed(Bracket::Round, 3),
+		]
+	);
+	assert_eq!(parsed[2].span, Span::new(input.len() - 3, input.len()));
+}
+
+/// `\left|x\right|` or `|x|`
+fn abs(input: Input) -> IResult<LatexToken> {
+	context(
+		"absolute value",
+		alt((
+			delimited(ws(tag(r"\left|")), tokens, closing(r"\right|")),
+			// `||` is two bars opening, not an empty absolute value
+			delimited(ws(tag("|")), some_tokens, ws(tag("|"))),
+		)),
+	)
+	.map(LatexToken::Abs)
+	.parse(input)
+}
+
+#[test]
+fn latex_abs() {
+	let input = r"\left| x \right| + |-2|";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::Abs(vec![LatexToken::Ident(Ident::AlphabeticChar('x')).into()]).into(),
+			LatexToken::Add.into(),
+			LatexToken::Abs(vec![
+				LatexToken::Neg.into(),
+				LatexToken::Num(2u32.into()).into()
+			])
+			.into(),
+		]
+	);
+	assert_eq!(parsed[2].span, Span::new(input.len() - 4, input.len()));
+
+	let input = "||l|z|||";
+	assert_eq!(parse_latex_lenient(input).0, parse_latex(input).unwrap());
+}
+
+/// Will error on content in numerator or denominator
+/// failing to parse
+fn frac(input: Input) -> IResult<LatexToken> {
+	context(
+		"fraction",
+		map(
+			(
+				ws(tag(r"\frac")),
+				required_group("numerator"),
+				required_group("denominator"),
+			),
+			|(_frac, numerator, denominator)| {
+				LatexToken::Frac(Frac {
+					numerator,
+					denominator,
+				})
+			},
+		),
+	)
+	.parse(input)
+}
+
+#[test]
 fn latex_frac() {
 	let fraction = r"\frac{2xy}{ 3 \pi }";
-	let res = Error::handle_parsing_errors(frac(fraction).finish(), fraction);
+	let res = handle_parsing_errors(frac(Input::new_extra(fraction, &State::strict(fraction))));
 	let Ok(tokens) = res else {
 		panic!("{}", res.unwrap_err());
 	};
@@ -423,11 +1879,681 @@ fn latex_frac() {
 		tokens,
 		LatexToken::Frac(Frac {
 			numerator: vec![
-				LatexToken::Num(2u32.into()),
-				Ident::AlphabeticChar('x').into(),
-				Ident::AlphabeticChar('y').into()
+				LatexToken::Num(2u32.into()).into(),
+				LatexToken::Ident(Ident::AlphabeticChar('x')).into(),
+				LatexToken::Ident(Ident::AlphabeticChar('y')).into()
+			],
+			denominator: vec![
+				LatexToken::Num(3u32.into()).into(),
+				LatexToken::Ident(Ident::Pi).into()
 			],
-			denominator: vec![LatexToken::Num(3u32.into()), Ident::Pi.into()],
 		})
 	);
 }
+
+/// Will error on content in index or radicand
+/// failing to parse
+fn root(input: Input) -> IResult<LatexToken> {
+	context(
+		"root",
+		map(
+			(
+				ws(tag(r"\sqrt")),
+				opt(ws(context(
+					"index",
+					delimited(tag("["), ws(tokens), closing("]")),
+				))),
+				required_group("radicand"),
+			),
+			|(_sqrt, index, radicand)| LatexToken::Root(Root { index, radicand }),
+		),
+	)
+	.parse(input)
+}
+
+#[test]
+fn latex_root() {
+	let input = r"\sqrt{2x} + \sqrt[3]{ 27 }";
+	let tokens = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		tokens,
+		vec![
+			LatexToken::Root(Root {
+				index: None,
+				radicand: vec![
+					LatexToken::Num(2u32.into()).into(),
+					LatexToken::Ident(Ident::AlphabeticChar('x')).into()
+				],
+			})
+			.into(),
+			LatexToken::Add.into(),
+			LatexToken::Root(Root {
+				index: Some(vec![LatexToken::Num(3u32.into()).into()]),
+				radicand: vec![LatexToken::Num(27u32.into()).into()],
+			})
+			.into(),
+		]
+	);
+}
+
+/// `(lower, upper)`
+type Scripts = (
+	Option<Vec<Spanned<LatexToken>>>,
+	Option<Vec<Spanned<LatexToken>>>,
+);
+
+/// `_{lower}^{upper}` in either order, both optional
+fn scripts(input: Input) -> IResult<Scripts> {
+	let lower = || preceded(ws(tag("_")), script);
+	let upper = || preceded(ws(tag("^")), script);
+	alt((
+		map((lower(), opt(upper())), |(lower, upper)| {
+			(Some(lower), upper)
+		}),
+		map((upper(), opt(lower())), |(upper, lower)| {
+			(lower, Some(upper))
+		}),
+		success((None, None)),
+	))
+	.parse(input)
+}
+
+/// Just an identifier, not any other token
+fn ident(input: Input) -> IResult<Ident> {
+	map_opt(identifier, |token| match token {
+		LatexToken::Ident(ident) => Some(ident),
+		_ => None,
+	})
+	.parse(input)
+}
+
+fn big_op(input: Input) -> IResult<LatexToken> {
+	let kind = |input| {
+		command(
+			input,
+			[BigOpKind::Sum, BigOpKind::Prod].map(|kind| (kind.name(), kind)),
+			r"\sum or \prod",
+		)
+	};
+	context(
+		"big operator",
+		map(
+			(preceded(multispace0, kind), scripts, ws(function_argument)),
+			|(kind, (lower, upper), body)| {
+				let (variable, lower) = match lower.as_deref() {
+					Some(
+						[
+							Spanned {
+								value: LatexToken::Ident(variable),
+								..
+							},
+						],
+					) => (Some(variable.clone()), None),
+					Some(
+						[
+							Spanned {
+								value: LatexToken::Ident(variable),
+								..
+							},
+							Spanned {
+								value: LatexToken::Relation(RelationKind::Eq),
+								..
+							},
+							bound @ ..,
+						],
+					) if !bound.is_empty() => (Some(variable.clone()), Some(bound.to_vec())),
+					_ => (None, lower),
+				};
+				LatexToken::BigOp(BigOp {
+					kind,
+					variable,
+					lower,
+					upper,
+					body,
+				})
+			},
+		),
+	)
+	.parse(input)
+}
+
+/// The `dx` at the end of an integral,
+/// also `\,dx` or `\mathrm{d}x`.
+///
+/// A bare `dx` has to be last in its term,
+/// so the `d y` in `\int a d y dx` is part of the body
+fn differential(input: Input) -> IResult<Ident> {
+	let marked = preceded(
+		(
+			multispace0,
+			alt((
+				tag(r"\mathrm{d}"),
+				preceded(
+					(tag(r"\,"), multispace0),
+					alt((tag(r"\mathrm{d}"), tag("d"))),
+				),
+			)),
+		),
+		ident,
+	);
+	let bare = terminated(preceded((multispace0, tag("d")), ident), peek(term_end));
+	alt((marked, bare)).parse(input)
+}
+
+/// The end of the latex or group, or an operator or relation
+fn term_end(input: Input) -> IResult<()> {
+	let closing = alt((
+		tag(")"),
+		tag("]"),
+		tag("}"),
+		tag("|"),
+		tag("&"),
+		tag(r"\right"),
+		tag(r"\end"),
+		tag(r"\\"),
+	));
+	alt((
+		value((), (multispace0, eof)),
+		value((), alt((add, neg, mul, relation))),
+		value((), preceded(multispace0, closing)),
+	))
+	.parse(input)
+}
+
+fn integral(input: Input) -> IResult<LatexToken> {
+	context(
+		"integral",
+		map(
+			(
+				preceded(multispace0, |input| command(input, [("int", ())], r"\int")),
+				scripts,
+				many_till(preceded(multispace0, spanned(token)), differential),
+			),
+			|(_int, (lower, upper), (body, variable))| {
+				LatexToken::Integral(Integral {
+					lower,
+					upper,
+					body,
+					variable,
+				})
+			},
+		),
+	)
+	.parse(input)
+}
+
+fn limit(input: Input) -> IResult<LatexToken> {
+	let to = |input| command(input, [("to", ()), ("rightarrow", ())], r"\to");
+	context(
+		"limit",
+		map(
+			(
+				preceded(multispace0, |input| command(input, [("lim", ())], r"\lim")),
+				preceded(
+					ws(tag("_")),
+					delimited(
+						ws(tag("{")),
+						(ident, preceded(ws(to), tokens)),
+						closing("}"),
+					),
+				),
+				ws(function_argument),
+			),
+			|(_lim, (variable, approaching), body)| {
+				LatexToken::Limit(Limit {
+					variable,
+					approaching,
+					body,
+				})
+			},
+		),
+	)
+	.parse(input)
+}
+
+#[test]
+fn latex_big_operators() {
+	let ident =
+		|char| -> Spanned<LatexToken> { LatexToken::Ident(Ident::AlphabeticChar(char)).into() };
+	let num = |num: u32| -> Spanned<LatexToken> { LatexToken::Num(num.into()).into() };
+	let exp = |num: u32| -> Spanned<LatexToken> {
+		LatexToken::Exp(vec![LatexToken::Num(num.into()).into()]).into()
+	};
+	let input = r"\sum_{i=1}^{n} i^{2} + \prod_k \left(k + 1\right) = \int_0^{1} x^{2} \,dx \lim_{h \to 0} \frac{h}{2}";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::BigOp(BigOp {
+				kind: BigOpKind::Sum,
+				variable: Some(Ident::AlphabeticChar('i')),
+				lower: Some(vec![num(1)]),
+				upper: Some(vec![ident('n')]),
+				body: vec![ident('i'), exp(2)],
+			})
+			.into(),
+			LatexToken::Add.into(),
+			LatexToken::BigOp(BigOp {
+				kind: BigOpKind::Prod,
+				variable: Some(Ident::AlphabeticChar('k')),
+				lower: None,
+				upper: None,
+				body: vec![ident('k'), LatexToken::Add.into(), num(1)],
+			})
+			.into(),
+			LatexToken::Relation(RelationKind::Eq).into(),
+			LatexToken::Integral(Integral {
+				lower: Some(vec![num(0)]),
+				upper: Some(vec![num(1)]),
+				body: vec![ident('x'), exp(2)],
+				variable: Ident::AlphabeticChar('x'),
+			})
+			.into(),
+			LatexToken::Limit(Limit {
+				variable: Ident::AlphabeticChar('h'),
+				approaching: vec![num(0)],
+				body: vec![
+					LatexToken::Frac(Frac {
+						numerator: vec![ident('h')],
+						denominator: vec![num(2)],
+					})
+					.into()
+				],
+			})
+			.into(),
+		]
+	);
+}
+
+#[test]
+fn latex_integral_differentials() {
+	let integral = |latex| {
+		let tokens = parse_latex(latex).unwrap();
+		let [
+			Spanned {
+				value: LatexToken::Integral(integral),
+				..
+			},
+			rest @ ..,
+		] = &tokens[..]
+		else {
+			panic!("Expected {} to start with an integral", latex);
+		};
+		(integral.clone(), rest.len())
+	};
+	let ident =
+		|char| -> Spanned<LatexToken> { LatexToken::Ident(Ident::AlphabeticChar(char)).into() };
+
+	// only the last `d` is the differential
+	let (parsed, rest) = integral(r"\int_0^1 a d y dx");
+	assert_eq!(parsed.body, vec![ident('a'), ident('d'), ident('y')]);
+	assert_eq!(parsed.variable, Ident::AlphabeticChar('x'));
+	assert_eq!(rest, 0);
+
+	// unless marked
+	let (parsed, rest) = integral(r"\int a \,dy x");
+	assert_eq!(parsed.body, vec![ident('a')]);
+	assert_eq!(parsed.variable, Ident::AlphabeticChar('y'));
+	assert_eq!(rest, 1);
+
+	// at the end of a term
+	for latex in [r"\int x dx + 1", r"\int x dx = 2"] {
+		assert_eq!(integral(latex).0.body, vec![ident('x')], "{}", latex);
+	}
+	assert!(parse_latex(r"\left(\int x dx\right)").is_ok());
+}
+
+#[test]
+fn bound_variables_are_not_visited() {
+	struct Idents(Vec<Ident>);
+	impl TokenVisitor for Idents {
+		fn visit_ident(&mut self, ident: &Ident) {
+			self.0.push(ident.clone());
+		}
+	}
+	let tokens =
+		LatexTokens::parse_from_latex(r"\sum_{i=1}^{n} \frac{i}{x} + \int_0^a t \cdot y dt").unwrap();
+	let mut idents = Idents(vec![]);
+	tokens.visit(&mut idents);
+	let chars: Vec<Ident> = "nxay".chars().map(Ident::AlphabeticChar).collect();
+	assert_eq!(idents.0, chars);
+
+	// also works as `dyn`
+	let mut idents = Idents(vec![]);
+	let visitor: &mut dyn TokenVisitor = &mut idents;
+	tokens.visit(visitor);
+	assert_eq!(idents.0, chars);
+
+	// nested, and the outer variable is bound in the inner bounds
+	let tokens =
+		LatexTokens::parse_from_latex(r"\sum_{i=1}^{n} \int_{0}^{i} t \cdot i \cdot z \,dt").unwrap();
+	let mut nested = Idents(vec![]);
+	tokens.visit(&mut nested);
+	assert_eq!(
+		nested.0,
+		vec![Ident::AlphabeticChar('n'), Ident::AlphabeticChar('z')]
+	);
+}
+
+#[test]
+fn bound_variables_are_not_visited_by_overridden_methods() {
+	#[derive(Default)]
+	struct Overridden {
+		idents: Vec<Ident>,
+		fracs: usize,
+	}
+	impl TokenVisitor for Overridden {
+		fn visit_ident(&mut self, ident: &Ident) {
+			self.idents.push(ident.clone());
+		}
+		fn visit_exp(&mut self, exp: &[Spanned<LatexToken>]) {
+			for token in exp {
+				token.visit(self);
+			}
+		}
+		fn visit_frac(&mut self, frac: &Frac) {
+			self.fracs += 1;
+			for token in frac.numerator.iter().chain(&frac.denominator) {
+				token.visit(self);
+			}
+		}
+	}
+	let tokens =
+		LatexTokens::parse_from_latex(r"\sum_{i=1}^{n} x^{i} \frac{i}{y} \frac{1}{z}").unwrap();
+	let mut visitor = Overridden::default();
+	tokens.visit(&mut visitor);
+	let chars: Vec<Ident> = "nxyz".chars().map(Ident::AlphabeticChar).collect();
+	assert_eq!(visitor.idents, chars);
+	assert_eq!(visitor.fracs, 2);
+}
+
+/// May be empty, like in `1 & & 3`
+fn cell(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	let rest = input.trim_start();
+	if ["&", r"\\", r"\end"]
+		.iter()
+		.any(|end| rest.starts_with(end))
+	{
+		return Ok((input, vec![]));
+	}
+	tokens(input)
+}
+
+fn matrix(input: Input) -> IResult<LatexToken> {
+	let kinds = MatrixKind::ALL.map(|kind| map(tag(kind.begin()), move |_begin| kind));
+	let (rest, kind) = preceded(multispace0, alt(kinds)).parse(input)?;
+	let row = separated_list1(ws(tag("&")), cell);
+	let (rest, mut rows) = context(
+		"matrix",
+		terminated(
+			separated_list1(ws(tag(r"\\")), spanned(row)),
+			closing(kind.end()),
+		),
+	)
+	.parse(rest)?;
+
+	// a trailing `\\` before `\end`
+	if rows.len() > 1
+		&& rows
+			.last()
+			.is_some_and(|row| matches!(row.as_slice(), [cell] if cell.is_empty()))
+	{
+		rows.pop();
+	}
+	let columns = rows[0].len();
+	if let Some(row) = rows.iter().find(|row| row.len() != columns) {
+		let (at_row, _before) =
+			nom::Input::take_split(&input, row.span.start - input.location_offset());
+		return Err(nom::Err::Error(NomError::expected(
+			at_row,
+			Expected::Description("the same number of cells in every row"),
+		)));
+	}
+	let rows = rows.into_iter().map(Spanned::into_inner).collect();
+	Ok((rest, LatexToken::Matrix(Matrix { kind, rows })))
+}
+
+#[test]
+fn latex_matrix() {
+	let num = |num: u32| -> Spanned<LatexToken> { LatexToken::Num(num.into()).into() };
+	let input = r"\begin{pmatrix} 1 & 2x \\ & 4 \\ \end{pmatrix}";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	let matrix = Matrix {
+		kind: MatrixKind::Round,
+		rows: vec![
+			vec![
+				vec![num(1)],
+				vec![num(2), LatexToken::Ident(Ident::AlphabeticChar('x')).into()],
+			],
+			vec![vec![], vec![num(4)]],
+		],
+	};
+	assert_eq!(matrix.shape(), (2, 2));
+	assert_eq!(parsed, vec![LatexToken::Matrix(matrix).into()]);
+
+	let Err(Error::ParsingError(err)) =
+		LatexTokens::parse_from_latex(r"\begin{bmatrix} 1 & 2 \\ 3 \end{bmatrix}")
+	else {
+		panic!("Expected rows of different lengths to error");
+	};
+	assert_eq!(err.offset, 25);
+	assert_eq!(
+		err.expected,
+		vec![Expected::Description(
+			"the same number of cells in every row"
+		)]
+	);
+
+	assert!(LatexTokens::parse_from_latex(r"\begin{bmatrix} 1 \end{pmatrix}").is_err());
+}
+
+/// `\sin` or `\operatorname{sin}`
+fn function_name(input: Input) -> IResult<FunctionName> {
+	let operatorname = map_opt(operatorname, |name| {
+		FunctionName::ALL
+			.into_iter()
+			.find(|function| function.name() == name)
+	});
+	alt((
+		|input| {
+			command(
+				input,
+				FunctionName::ALL.map(|name| (name.name(), name)),
+				r"a function like \sin",
+			)
+		},
+		operatorname,
+	))
+	.parse(input)
+}
+
+/// `\text{kg}`, kept exactly
+fn text(input: Input) -> IResult<LatexToken> {
+	map(
+		preceded(
+			(multispace0, tag(r"\text"), multispace0, tag("{")),
+			terminated(take_till(|char| char == '}'), closing("}")),
+		),
+		|text: Input| LatexToken::Text(text.to_string()),
+	)
+	.parse(input)
+}
+
+#[test]
+fn latex_operatorname_and_text() {
+	let input = r"\operatorname{speed}_{max} = 5\text{m/s} \operatorname{sin} x";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::Ident(Ident::Subscripted {
+				base: Box::new(Ident::Named("speed".to_owned())),
+				subscript: "max".to_owned(),
+			})
+			.into(),
+			LatexToken::Relation(RelationKind::Eq).into(),
+			LatexToken::Num(5u32.into()).into(),
+			LatexToken::Text("m/s".to_owned()).into(),
+			LatexToken::Function(Function {
+				name: FunctionName::Sin,
+				subscript: None,
+				power: None,
+				argument: vec![LatexToken::Ident(Ident::AlphabeticChar('x')).into()],
+			})
+			.into(),
+		]
+	);
+}
+
+/// The content after a `_` or `^`,
+/// either `{...}` or a single character
+fn script(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	alt((
+		group("script"),
+		map(spanned(satisfy(|char| char.is_ascii_digit())), |digit| {
+			vec![digit.map(|digit| LatexToken::Num(BigUint::from(digit as u32 - '0' as u32)))]
+		}),
+		map(spanned(alphanumeric_ident), |ident| vec![ident]),
+	))
+	.parse(input)
+}
+
+/// Either bracketed, or a run of implicitly multiplied terms
+/// like the `2x` in `\sin 2x`
+fn function_argument(input: Input) -> IResult<Vec<Spanned<LatexToken>>> {
+	limited(alt((
+		delimited(ws(tag(r"\left(")), tokens, closing(r"\right)")),
+		delimited(ws(tag("(")), tokens, closing(")")),
+		map(
+			(
+				spanned(alt((
+					num, identifier, frac, root, function, big_op, integral, limit,
+				))),
+				many0(spanned(alt((num, identifier, frac, root, exp)))),
+			),
+			|(first, rest)| std::iter::once(first).chain(rest).collect(),
+		),
+	)))
+	.parse(input)
+}
+
+fn function(input: Input) -> IResult<LatexToken> {
+	context(
+		"function",
+		map(
+			(
+				preceded(multispace0, function_name),
+				scripts,
+				ws(function_argument),
+			),
+			|(name, (subscript, power), argument)| {
+				LatexToken::Function(Function {
+					name,
+					subscript,
+					power,
+					argument,
+				})
+			},
+		),
+	)
+	.parse(input)
+}
+
+#[test]
+fn latex_function() {
+	let input = r"\sin 2x + \log_{2}\left( 8 \right) \cos\pi";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::Function(Function {
+				name: FunctionName::Sin,
+				subscript: None,
+				power: None,
+				argument: vec![
+					LatexToken::Num(2u32.into()).into(),
+					LatexToken::Ident(Ident::AlphabeticChar('x')).into()
+				],
+			})
+			.into(),
+			LatexToken::Add.into(),
+			LatexToken::Function(Function {
+				name: FunctionName::Log,
+				subscript: Some(vec![LatexToken::Num(2u32.into()).into()]),
+				power: None,
+				argument: vec![LatexToken::Num(8u32.into()).into()],
+			})
+			.into(),
+			LatexToken::Function(Function {
+				name: FunctionName::Cos,
+				subscript: None,
+				power: None,
+				argument: vec![LatexToken::Ident(Ident::Pi).into()],
+			})
+			.into(),
+		]
+	);
+
+	let input = r"\sin^{2} x + \log_{2}^{3}\left(x\right)";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	let x = || vec![LatexToken::Ident(Ident::AlphabeticChar('x')).into()];
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::Function(Function {
+				name: FunctionName::Sin,
+				subscript: None,
+				power: Some(vec![LatexToken::Num(2u32.into()).into()]),
+				argument: x(),
+			})
+			.into(),
+			LatexToken::Add.into(),
+			LatexToken::Function(Function {
+				name: FunctionName::Log,
+				subscript: Some(vec![LatexToken::Num(2u32.into()).into()]),
+				power: Some(vec![LatexToken::Num(3u32.into()).into()]),
+				argument: x(),
+			})
+			.into(),
+		]
+	);
+
+	let input = r"\sinh x";
+	let parsed = assert_parsing_errors(tokens.parse(Input::new_extra(input, &State::strict(input))));
+	assert_eq!(
+		parsed,
+		vec![
+			LatexToken::Function(Function {
+				name: FunctionName::Sinh,
+				subscript: None,
+				power: None,
+				argument: vec![LatexToken::Ident(Ident::AlphabeticChar('x')).into()],
+			})
+			.into()
+		]
+	);
+}
+
+#[cfg(test)]
+proptest::proptest! {
+	/// Including multi-byte characters anywhere
+	#[test]
+	fn parsing_never_panics(
+		input in proptest::prop_oneof![proptest::arbitrary::any::<String>(), strategies::latex_like()]
+	) {
+		let strict = parse_latex(&input);
+		let (lenient, errors) = parse_latex_lenient(&input);
+		for error in &errors {
+			proptest::prop_assert!(input.get(error.span().range()).is_some(), "{:?}", error);
+		}
+		for token in &lenient {
+			proptest::prop_assert!(input.get(token.span.range()).is_some(), "{:?}", token);
+		}
+		if let Ok(strict) = strict {
+			proptest::prop_assert!(errors.is_empty(), "{:?}", errors);
+			proptest::prop_assert_eq!(lenient, strict);
+		}
+		_ = Macros::new().expand(&input);
+		_ = asciimath::parse_asciimath(&input);
+	}
+}
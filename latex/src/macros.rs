@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+
+use crate::{LatexToken, ParsingError, Span, Spanned, TokenVisitorMut};
+
+/// Stops macros like `\newcommand{\a}{\a\a}` from hanging the parser
+const MAX_EXPANSIONS: usize = 10_000;
+/// Stops macros like `\newcommand{\a}{\a}` from overflowing the stack
+const MAX_DEPTH: usize = 64;
+
+/// Macros to expand before parsing,
+/// either defined from rust with [`Macros::define`]
+/// or in the latex with `\newcommand`
+#[derive(Debug, Clone, Default)]
+pub struct Macros(HashMap<String, Macro>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Macro {
+	/// Substituted for `#1` to `#9` in [`Self::body`]
+	pub arguments: u8,
+	pub body: String,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MacroError {
+	#[error("\\{name} expands too many times, does it use itself?")]
+	TooManyExpansions { name: String, span: Span },
+
+	#[error("\\{name} needs {arguments} arguments")]
+	MissingArgument {
+		name: String,
+		arguments: u8,
+		span: Span,
+	},
+
+	#[error("Expected \\newcommand{{\\name}}[arguments]{{body}}")]
+	InvalidDefinition { span: Span },
+}
+
+impl MacroError {
+	/// Where the macro is used in the source latex,
+	/// even if the error came from expanding another macro inside it
+	pub fn span(&self) -> Span {
+		match self {
+			MacroError::TooManyExpansions { span, .. }
+			| MacroError::MissingArgument { span, .. }
+			| MacroError::InvalidDefinition { span } => *span,
+		}
+	}
+}
+
+impl Macros {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// `name` is without the leading `\`.
+	///
+	/// Panics if there are more than 9 arguments
+	pub fn define(
+		&mut self,
+		name: impl Into<String>,
+		arguments: u8,
+		body: impl Into<String>,
+	) -> &mut Self {
+		assert!(arguments <= 9, "Macros can only have up to 9 arguments");
+		self.0.insert(
+			name.into(),
+			Macro {
+				arguments,
+				body: body.into(),
+			},
+		);
+		self
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Macro> {
+		self.0.get(name)
+	}
+
+	/// Expands every macro, including ones defined in `latex` with `\newcommand`
+	/// (or `\renewcommand`), which are only used after their definition
+	/// and are removed from the output
+	pub fn expand(&self, latex: &str) -> Result<String, MacroError> {
+		self.expand_mapped(latex).map(|expanded| expanded.latex)
+	}
+
+	/// Like [`Self::expand`], remembering where each piece of the expanded latex came from
+	pub(crate) fn expand_mapped(&self, latex: &str) -> Result<Expanded, MacroError> {
+		let mut macros = self.clone();
+		let mut expansions = 0;
+		let mut pieces = Vec::new();
+		let expanded = macros.expand_in(latex, None, 0, &mut expansions, Some(&mut pieces))?;
+		Ok(Expanded {
+			latex: expanded,
+			pieces,
+			source_len: latex.len(),
+		})
+	}
+
+	/// `origin` is the span of the top level macro being expanded, if any,
+	/// and only the top level records `pieces`
+	fn expand_in(
+		&mut self,
+		latex: &str,
+		origin: Option<Span>,
+		depth: usize,
+		expansions: &mut usize,
+		mut pieces: Option<&mut Vec<Piece>>,
+	) -> Result<String, MacroError> {
+		let mut expanded = String::with_capacity(latex.len());
+		let mut push = |expanded: &mut String, text: &str, source: Span, copied: bool| {
+			if let Some(pieces) = pieces.as_deref_mut()
+				&& !text.is_empty()
+			{
+				pieces.push(Piece {
+					start: expanded.len(),
+					source,
+					copied,
+				});
+			}
+			expanded.push_str(text);
+		};
+		let mut i = 0;
+		while let Some(found) = latex[i..].find('\\') {
+			let start = i + found;
+			push(&mut expanded, &latex[i..start], Span::new(i, start), true);
+			let after_slash = &latex[start + 1..];
+			let name_len = after_slash
+				.find(|char: char| !char.is_alphabetic())
+				.unwrap_or(after_slash.len());
+			if name_len == 0 {
+				// e.g. `\{`, which can't be a macro
+				let len = 1 + after_slash.chars().next().map_or(0, char::len_utf8);
+				push(
+					&mut expanded,
+					&latex[start..start + len],
+					Span::new(start, start + len),
+					true,
+				);
+				i = start + len;
+				continue;
+			}
+			let name = &after_slash[..name_len];
+			let mut end = start + 1 + name_len;
+			let span = |end| origin.unwrap_or(Span::new(start, end));
+
+			if name == "newcommand" || name == "renewcommand" {
+				let (definition_end, name, definition) =
+					definition(latex, end).ok_or(MacroError::InvalidDefinition { span: span(end) })?;
+				self.0.insert(name, definition);
+				i = definition_end;
+				continue;
+			}
+
+			let Some(definition) = self.0.get(name).cloned() else {
+				push(
+					&mut expanded,
+					&latex[start..end],
+					Span::new(start, end),
+					true,
+				);
+				i = end;
+				continue;
+			};
+			let mut arguments = Vec::new();
+			for _ in 0..definition.arguments {
+				let (argument, argument_end) =
+					argument(latex, end).ok_or_else(|| MacroError::MissingArgument {
+						name: name.to_owned(),
+						arguments: definition.arguments,
+						span: span(end),
+					})?;
+				arguments.push(argument);
+				end = argument_end;
+			}
+			*expansions += 1;
+			if *expansions > MAX_EXPANSIONS || depth >= MAX_DEPTH {
+				return Err(MacroError::TooManyExpansions {
+					name: name.to_owned(),
+					span: span(end),
+				});
+			}
+			let body = substitute(&definition.body, &arguments);
+			let body = self.expand_in(&body, Some(span(end)), depth + 1, expansions, None)?;
+			push(&mut expanded, &body, Span::new(start, end), false);
+			i = end;
+		}
+		push(&mut expanded, &latex[i..], Span::new(i, latex.len()), true);
+		Ok(expanded)
+	}
+}
+
+/// Latex with its macros expanded,
+/// which can map spans into it back to the source latex
+#[derive(Debug)]
+pub(crate) struct Expanded {
+	pub(crate) latex: String,
+	/// Covering all of [`Self::latex`] in order
+	pieces: Vec<Piece>,
+	source_len: usize,
+}
+
+/// Part of [`Expanded::latex`] from `start` until the next piece
+#[derive(Debug)]
+struct Piece {
+	start: usize,
+	source: Span,
+	/// Copied byte for byte from `source`,
+	/// otherwise a macro used at `source` expanded to this
+	copied: bool,
+}
+
+impl Expanded {
+	/// Where `offset` came from in the source latex,
+	/// either end of a macro's use depending on if `offset` is the `end` of a span
+	fn source_offset(&self, offset: usize, end: bool) -> usize {
+		if offset >= self.latex.len() {
+			return self.source_len;
+		}
+		// the end of a span is after the byte it covers
+		let covered = if end {
+			offset.saturating_sub(1)
+		} else {
+			offset
+		};
+		let piece = &self.pieces[self.pieces.partition_point(|piece| piece.start <= covered) - 1];
+		match (piece.copied, end) {
+			(true, _) => piece.source.start + (offset - piece.start),
+			(false, false) => piece.source.start,
+			(false, true) => piece.source.end,
+		}
+	}
+
+	pub(crate) fn source_span(&self, span: Span) -> Span {
+		let start = self.source_offset(span.start, false);
+		let end = self.source_offset(span.end, true);
+		Span::new(start, end.max(start))
+	}
+
+	pub(crate) fn source_error(&self, mut err: ParsingError) -> ParsingError {
+		err.offset = self.source_offset(err.offset, false);
+		err
+	}
+}
+
+/// Spans are mapped back to the source latex
+impl TokenVisitorMut for &Expanded {
+	fn visit_token_mut(&mut self, token: &mut Spanned<LatexToken>) {
+		token.span = self.source_span(token.span);
+		if let LatexToken::Error(span) = &mut token.value {
+			*span = self.source_span(*span);
+		}
+		token.value.visit_mut(self);
+	}
+}
+
+/// After `\newcommand`, `{\name}[arguments]{body}` or `\name{body}`,
+/// returning where the definition ends
+fn definition(latex: &str, start: usize) -> Option<(usize, String, Macro)> {
+	let mut i = skip_whitespace(latex, start);
+	let name = match group(latex, i) {
+		Some((name, end)) => {
+			i = end;
+			name.trim()
+		}
+		None if latex[i..].starts_with('\\') => {
+			let name_len = latex[i + 1..]
+				.find(|char: char| !char.is_alphabetic())
+				.unwrap_or(latex.len() - i - 1);
+			let name = &latex[i..i + 1 + name_len];
+			i += name.len();
+			name
+		}
+		None => return None,
+	};
+	let name = name.strip_prefix('\\')?;
+	if name.is_empty() || !name.chars().all(char::is_alphabetic) {
+		return None;
+	}
+
+	i = skip_whitespace(latex, i);
+	let mut arguments = 0;
+	if let Some(rest) = latex[i..].strip_prefix('[') {
+		let (count, rest) = rest.split_once(']')?;
+		arguments = count.trim().parse().ok().filter(|count| *count <= 9)?;
+		i = latex.len() - rest.len();
+	}
+
+	let (body, end) = group(latex, skip_whitespace(latex, i))?;
+	// every `#n` must be an argument
+	let mut placeholders = body.match_indices('#');
+	if placeholders.any(|(hash, _)| {
+		body[hash + 1..]
+			.chars()
+			.next()
+			.and_then(|digit| digit.to_digit(10))
+			.is_none_or(|digit| digit == 0 || digit > arguments as u32)
+	}) {
+		return None;
+	}
+
+	let definition = Macro {
+		arguments,
+		body: body.to_owned(),
+	};
+	Some((end, name.to_owned(), definition))
+}
+
+/// A `{group}`, a command like `\alpha`, or one character
+fn argument(latex: &str, start: usize) -> Option<(String, usize)> {
+	let start = skip_whitespace(latex, start);
+	if let Some((inner, end)) = group(latex, start) {
+		return Some((inner.to_owned(), end));
+	}
+	let rest = &latex[start..];
+	let len = match rest.strip_prefix('\\') {
+		Some(after_slash) => match after_slash.find(|char: char| !char.is_alphabetic()) {
+			Some(0) => 1 + after_slash.chars().next()?.len_utf8(),
+			Some(name_len) => 1 + name_len,
+			None => rest.len(),
+		},
+		None => rest.chars().next()?.len_utf8(),
+	};
+	Some((rest[..len].to_owned(), start + len))
+}
+
+/// The inside of balanced `{ }` starting at `start`, and where it ends
+fn group(latex: &str, start: usize) -> Option<(&str, usize)> {
+	let inner_start = start + latex[start..].strip_prefix('{').map(|_| 1)?;
+	let mut depth = 1;
+	let mut chars = latex[inner_start..].char_indices();
+	while let Some((i, char)) = chars.next() {
+		match char {
+			// `\{` and `\}` don't count
+			'\\' => {
+				chars.next();
+			}
+			'{' => depth += 1,
+			'}' => {
+				depth -= 1;
+				if depth == 0 {
+					let inner_end = inner_start + i;
+					return Some((&latex[inner_start..inner_end], inner_end + 1));
+				}
+			}
+			_ => {}
+		}
+	}
+	None
+}
+
+fn skip_whitespace(latex: &str, start: usize) -> usize {
+	latex.len() - latex[start..].trim_start().len()
+}
+
+/// Replaces `#1` to `#9` with `arguments`
+fn substitute(body: &str, arguments: &[String]) -> String {
+	let mut substituted = String::with_capacity(body.len());
+	let mut chars = body.chars().peekable();
+	while let Some(char) = chars.next() {
+		let argument = chars
+			.peek()
+			.and_then(|digit| digit.to_digit(10))
+			.and_then(|digit| arguments.get((digit as usize).checked_sub(1)?));
+		match (char, argument) {
+			('#', Some(argument)) => {
+				chars.next();
+				// so `\alpha#1` with `x` doesn't become `\alphax`
+				if ends_with_command(&substituted) && argument.starts_with(char::is_alphabetic) {
+					substituted.push(' ');
+				}
+				substituted.push_str(argument);
+			}
+			(char, _) => substituted.push(char),
+		}
+	}
+	substituted
+}
+
+fn ends_with_command(latex: &str) -> bool {
+	let without_name = latex.trim_end_matches(char::is_alphabetic);
+	without_name.len() < latex.len() && without_name.ends_with('\\')
+}
+
+#[test]
+fn macro_expansion() {
+	let mut macros = Macros::new();
+	macros.define("half", 0, r"\frac{1}{2}");
+	macros.define("sq", 1, r"#1^{2}");
+	assert_eq!(
+		macros.expand(r"\half \sq{x + 1} \sq y \sq\pi").unwrap(),
+		r"\frac{1}{2} x + 1^{2} y^{2} \pi^{2}"
+	);
+
+	let latex = r"\newcommand{\avg}[2]{\frac{#1 + #2}{2}} \renewcommand\half{0.5} \avg{a}{\half}";
+	assert_eq!(macros.expand(latex).unwrap(), r"  \frac{a + 0.5}{2}");
+	// only used after they are defined
+	assert_eq!(
+		macros.expand(r"\avg{1}{2} \newcommand{\avg}{3}").unwrap(),
+		r"\avg{1}{2} "
+	);
+}
+
+#[test]
+fn macro_errors() {
+	let macros = Macros::new();
+	let Err(MacroError::TooManyExpansions { name, span }) =
+		macros.expand(r"x \newcommand{\a}{\a \a} + \a")
+	else {
+		panic!("Expected recursion to error");
+	};
+	assert_eq!(name, "a");
+	assert_eq!(span, Span::new(27, 29));
+
+	let Err(err) = macros.expand(r"\newcommand{\f}[2]{#1 #2} \f{x}") else {
+		panic!("Expected a missing argument to error");
+	};
+	assert_eq!(
+		err,
+		MacroError::MissingArgument {
+			name: "f".to_owned(),
+			arguments: 2,
+			span: Span::new(26, 31),
+		}
+	);
+
+	assert!(matches!(
+		macros.expand(r"\newcommand{\f}{#1}"),
+		Err(MacroError::InvalidDefinition { .. })
+	));
+}
+
+#[test]
+fn parse_with_macros() {
+	let mut macros = Macros::new();
+	macros.define("sq", 1, r"#1^{2}");
+	let tokens = crate::LatexTokens::parse_from_latex_with_macros(r"\sq{x} = \sq2", &macros).unwrap();
+	let expected = crate::LatexTokens::parse_from_latex(r"x^{2} = 2^{2}").unwrap();
+	let values = |tokens: &crate::LatexTokens| {
+		tokens
+			.tokens()
+			.iter()
+			.map(|token| token.value.clone())
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(values(&tokens), values(&expected));
+	// everything a macro expanded to spans where it was used
+	let spans: Vec<Span> = tokens.tokens().iter().map(|token| token.span).collect();
+	assert_eq!(
+		spans,
+		vec![
+			Span::new(0, 6),
+			Span::new(0, 6),
+			Span::new(7, 8),
+			Span::new(9, 13),
+			Span::new(9, 13)
+		]
+	);
+
+	// removing a definition doesn't shift what's after it
+	let latex = r"\newcommand{\half}{0.5} y = \half + )";
+	let Err(crate::Error::ParsingError(err)) = crate::LatexTokens::parse_from_latex(latex) else {
+		panic!("Expected a parsing error");
+	};
+	assert_eq!(err.offset, latex.find(')').unwrap());
+	let latex = r"\newcommand{\half}{0.5} y";
+	let tokens = crate::LatexTokens::parse_from_latex(latex).unwrap();
+	let y = latex.find('y').unwrap();
+	assert_eq!(tokens.tokens()[0].span, Span::new(y, y + 1));
+}
@@ -0,0 +1,280 @@
+//! Presentation MathML, so parsed latex can be shown
+//! without MathQuill and read by screen readers
+
+use crate::{
+	BigOp, BigOpKind, Bracket, Bracketed, Decimal, Frac, Function, Ident, Integral, LatexToken,
+	LatexTokens, Limit, Matrix, MatrixKind, RelationKind, Root, Spanned, to_latex::plain_decimal,
+};
+
+/// A whole `<math>` element
+pub fn to_mathml(tokens: &LatexTokens) -> String {
+	format!(
+		r#"<math xmlns="http://www.w3.org/1998/Math/MathML">{}</math>"#,
+		row(tokens.tokens())
+	)
+}
+
+impl LatexTokens {
+	/// See [`to_mathml`]
+	pub fn to_mathml(&self) -> String {
+		to_mathml(self)
+	}
+}
+
+/// An `<mrow>` of every token.
+///
+/// [`LatexToken::Exp`] has no base of its own,
+/// so it takes the element before it as the base of an `<msup>`
+fn row(tokens: &[Spanned<LatexToken>]) -> String {
+	let mut elements: Vec<String> = Vec::new();
+	for token in tokens {
+		match &token.value {
+			LatexToken::Exp(exp) => {
+				let base = elements.pop().unwrap_or_else(|| "<mrow></mrow>".to_owned());
+				elements.push(format!("<msup>{}{}</msup>", base, row(exp)));
+			}
+			token => elements.push(element(token)),
+		}
+	}
+	format!("<mrow>{}</mrow>", elements.concat())
+}
+
+fn element(token: &LatexToken) -> String {
+	match token {
+		LatexToken::Num(num) => format!("<mn>{}</mn>", num),
+		LatexToken::Decimal(decimal) => decimal_element(decimal),
+		LatexToken::Neg => "<mo>−</mo>".to_owned(),
+		LatexToken::Mul => "<mo>·</mo>".to_owned(),
+		LatexToken::Add => "<mo>+</mo>".to_owned(),
+		LatexToken::Relation(relation) => format!("<mo>{}</mo>", relation_symbol(*relation)),
+		LatexToken::Exp(exp) => format!("<msup><mrow></mrow>{}</msup>", row(exp)),
+		LatexToken::Ident(ident) => ident_element(ident),
+		LatexToken::Bracketed(Bracketed { bracket, inner }) => {
+			let (left, right) = match bracket {
+				Bracket::Round => ("(", ")"),
+				Bracket::Square => ("[", "]"),
+				Bracket::Curly => ("{", "}"),
+			};
+			fenced(left, row(inner), right)
+		}
+		LatexToken::Abs(inner) => fenced("|", row(inner), "|"),
+		LatexToken::Frac(Frac {
+			numerator,
+			denominator,
+		}) => format!("<mfrac>{}{}</mfrac>", row(numerator), row(denominator)),
+		LatexToken::Root(Root {
+			index: None,
+			radicand,
+		}) => format!("<msqrt>{}</msqrt>", row(radicand)),
+		LatexToken::Root(Root {
+			index: Some(index),
+			radicand,
+		}) => format!("<mroot>{}{}</mroot>", row(radicand), row(index)),
+		LatexToken::Function(Function {
+			name,
+			subscript,
+			power,
+			argument,
+		}) => {
+			let name = format!("<mi>{}</mi>", name.name());
+			let name = match (subscript, power) {
+				(Some(subscript), Some(power)) => {
+					format!(
+						"<msubsup>{}{}{}</msubsup>",
+						name,
+						row(subscript),
+						row(power)
+					)
+				}
+				(Some(subscript), None) => format!("<msub>{}{}</msub>", name, row(subscript)),
+				(None, Some(power)) => format!("<msup>{}{}</msup>", name, row(power)),
+				(None, None) => name,
+			};
+			// invisible function application
+			format!(
+				"{}<mo>&#x2061;</mo>{}",
+				name,
+				fenced("(", row(argument), ")")
+			)
+		}
+		LatexToken::BigOp(BigOp {
+			kind,
+			variable,
+			lower,
+			upper,
+			body,
+		}) => {
+			let symbol = match kind {
+				BigOpKind::Sum => "<mo>∑</mo>",
+				BigOpKind::Prod => "<mo>∏</mo>",
+			};
+			let lower = match (variable, lower) {
+				(Some(variable), Some(lower)) => Some(format!(
+					"<mrow>{}<mo>=</mo>{}</mrow>",
+					ident_element(variable),
+					row(lower)
+				)),
+				(Some(variable), None) => Some(ident_element(variable)),
+				(None, lower) => lower.as_deref().map(row),
+			};
+			format!(
+				"{}{}",
+				under_over(symbol, lower, upper.as_deref().map(row)),
+				row(body)
+			)
+		}
+		LatexToken::Integral(Integral {
+			lower,
+			upper,
+			body,
+			variable,
+		}) => {
+			let lower = lower.as_deref().map(row);
+			let upper = upper.as_deref().map(row);
+			let symbol = match (lower, upper) {
+				(Some(lower), Some(upper)) => {
+					format!("<msubsup><mo>∫</mo>{}{}</msubsup>", lower, upper)
+				}
+				(Some(lower), None) => format!("<msub><mo>∫</mo>{}</msub>", lower),
+				(None, Some(upper)) => format!("<msup><mo>∫</mo>{}</msup>", upper),
+				(None, None) => "<mo>∫</mo>".to_owned(),
+			};
+			format!(
+				"{}{}<mi>d</mi>{}",
+				symbol,
+				row(body),
+				ident_element(variable)
+			)
+		}
+		LatexToken::Limit(Limit {
+			variable,
+			approaching,
+			body,
+		}) => {
+			let under = format!(
+				"<mrow>{}<mo>→</mo>{}</mrow>",
+				ident_element(variable),
+				row(approaching)
+			);
+			format!(
+				"{}{}",
+				under_over("<mo>lim</mo>", Some(under), None),
+				row(body)
+			)
+		}
+		LatexToken::Matrix(Matrix { kind, rows }) => {
+			let table: String = rows
+				.iter()
+				.map(|cells| {
+					let cells: String = cells
+						.iter()
+						.map(|cell| format!("<mtd>{}</mtd>", row(cell)))
+						.collect();
+					format!("<mtr>{}</mtr>", cells)
+				})
+				.collect();
+			let table = format!("<mtable>{}</mtable>", table);
+			match kind {
+				MatrixKind::Plain => table,
+				MatrixKind::Round => fenced("(", table, ")"),
+				MatrixKind::Square => fenced("[", table, "]"),
+				MatrixKind::Curly => fenced("{", table, "}"),
+				MatrixKind::Bars => fenced("|", table, "|"),
+				MatrixKind::DoubleBars => fenced("‖", table, "‖"),
+			}
+		}
+		LatexToken::Text(text) => format!("<mtext>{}</mtext>", escape(text)),
+		LatexToken::Error(_) => "<merror><mtext>?</mtext></merror>".to_owned(),
+	}
+}
+
+fn under_over(symbol: &str, under: Option<String>, over: Option<String>) -> String {
+	match (under, over) {
+		(Some(under), Some(over)) => {
+			format!("<munderover>{}{}{}</munderover>", symbol, under, over)
+		}
+		(Some(under), None) => format!("<munder>{}{}</munder>", symbol, under),
+		(None, Some(over)) => format!("<mover>{}{}</mover>", symbol, over),
+		(None, None) => symbol.to_owned(),
+	}
+}
+
+fn fenced(left: &str, inner: String, right: &str) -> String {
+	format!("<mrow><mo>{}</mo>{}<mo>{}</mo></mrow>", left, inner, right)
+}
+
+/// `0.050` if the exponent is a little negative,
+/// otherwise scientific notation with one digit before the point like `6.02×10²³`
+fn decimal_element(decimal: &Decimal) -> String {
+	if let Some(plain) = plain_decimal(decimal) {
+		return format!("<mn>{}</mn>", plain);
+	}
+	let digits = decimal.mantissa.to_string();
+	let (first, rest) = digits.split_at(1);
+	let mantissa = match rest.is_empty() {
+		true => first.to_owned(),
+		false => format!("{}.{}", first, rest),
+	};
+	let exponent = decimal.exponent.saturating_add(rest.len() as i64);
+	let exponent = match exponent < 0 {
+		true => format!(
+			"<mrow><mo>−</mo><mn>{}</mn></mrow>",
+			exponent.unsigned_abs()
+		),
+		false => format!("<mn>{}</mn>", exponent),
+	};
+	format!(
+		"<mn>{}</mn><mo>×</mo><msup><mn>10</mn>{}</msup>",
+		mantissa, exponent
+	)
+}
+
+fn relation_symbol(relation: RelationKind) -> String {
+	match relation {
+		RelationKind::Lt => "&lt;".to_owned(),
+		RelationKind::Gt => "&gt;".to_owned(),
+		relation => relation.to_string(),
+	}
+}
+
+fn escape(text: &str) -> String {
+	text
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+}
+
+fn ident_element(ident: &Ident) -> String {
+	match ident {
+		Ident::Subscripted { base, subscript } => {
+			let subscript = if subscript.chars().all(|char| char.is_ascii_digit()) {
+				format!("<mn>{}</mn>", subscript)
+			} else {
+				format!("<mi>{}</mi>", subscript)
+			};
+			format!("<msub>{}{}</msub>", ident_element(base), subscript)
+		}
+		ident => format!("<mi>{}</mi>", ident),
+	}
+}
+
+#[test]
+fn mathml() {
+	let mathml = |latex| LatexTokens::parse_from_latex(latex).unwrap().to_mathml();
+	insta::assert_snapshot!(
+		mathml(r"2x^{2} - \frac{\pi}{3} < \sqrt{y_1}"),
+		@r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow><mn>2</mn><msup><mi>x</mi><mrow><mn>2</mn></mrow></msup><mo>−</mo><mfrac><mrow><mi>π</mi></mrow><mrow><mn>3</mn></mrow></mfrac><mo>&lt;</mo><msqrt><mrow><msub><mi>y</mi><mn>1</mn></msub></mrow></msqrt></mrow></math>"#
+	);
+	insta::assert_snapshot!(
+		mathml(r"\log_{2}\left(8\right) \cdot |\alpha| \approx 6.02\times10^{23}"),
+		@r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow><msub><mi>log</mi><mrow><mn>2</mn></mrow></msub><mo>&#x2061;</mo><mrow><mo>(</mo><mrow><mn>8</mn></mrow><mo>)</mo></mrow><mo>·</mo><mrow><mo>|</mo><mrow><mi>α</mi></mrow><mo>|</mo></mrow><mo>≈</mo><mn>6.02</mn><mo>×</mo><msup><mn>10</mn><mn>23</mn></msup></mrow></math>"#
+	);
+	insta::assert_snapshot!(
+		mathml(r"0.050 + 1\times10^{-9999} + 0.00000000012"),
+		@r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow><mn>0.050</mn><mo>+</mo><mn>1</mn><mo>×</mo><msup><mn>10</mn><mrow><mo>−</mo><mn>9999</mn></mrow></msup><mo>+</mo><mn>1.2</mn><mo>×</mo><msup><mn>10</mn><mrow><mo>−</mo><mn>10</mn></mrow></msup></mrow></math>"#
+	);
+	insta::assert_snapshot!(
+		mathml(r"\sin^{2} x"),
+		@r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow><msup><mi>sin</mi><mrow><mn>2</mn></mrow></msup><mo>&#x2061;</mo><mrow><mo>(</mo><mrow><mi>x</mi></mrow><mo>)</mo></mrow></mrow></math>"#
+	);
+}
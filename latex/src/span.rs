@@ -0,0 +1,107 @@
+use std::ops::{Deref, DerefMut, Range};
+
+/// A range of byte offsets into the source latex
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+	pub start: usize,
+	/// Exclusive
+	pub end: usize,
+}
+
+impl Span {
+	pub fn new(start: usize, end: usize) -> Self {
+		Span { start, end }
+	}
+
+	pub fn len(&self) -> usize {
+		self.end - self.start
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.start == self.end
+	}
+
+	/// The smallest span covering both `self` and `other`
+	pub fn join(self, other: Span) -> Span {
+		Span::new(self.start.min(other.start), self.end.max(other.end))
+	}
+
+	pub fn range(self) -> Range<usize> {
+		self.start..self.end
+	}
+}
+
+impl From<Range<usize>> for Span {
+	fn from(range: Range<usize>) -> Self {
+		Span::new(range.start, range.end)
+	}
+}
+
+/// A value along with the [`Span`] of latex it was parsed from.
+///
+/// Spans are ignored when comparing,
+/// so tokens parsed from differently formatted latex are still equal
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spanned<T> {
+	pub span: Span,
+	pub value: T,
+}
+
+impl<T> Spanned<T> {
+	pub fn new(value: T, span: Span) -> Self {
+		Spanned { span, value }
+	}
+
+	pub fn into_inner(self) -> T {
+		self.value
+	}
+
+	pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+		Spanned {
+			span: self.span,
+			value: f(self.value),
+		}
+	}
+}
+
+/// Uses a default [`Span`],
+/// for building tokens by hand
+impl<T> From<T> for Spanned<T> {
+	fn from(value: T) -> Self {
+		Spanned {
+			span: Span::default(),
+			value,
+		}
+	}
+}
+
+impl<T> Deref for Spanned<T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		&self.value
+	}
+}
+
+impl<T> DerefMut for Spanned<T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.value
+	}
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.value == other.value
+	}
+}
+
+impl<T: Eq> Eq for Spanned<T> {}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Spanned<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.value.fmt(f)?;
+		write!(f, " @ {}..{}", self.span.start, self.span.end)
+	}
+}
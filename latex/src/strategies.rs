@@ -0,0 +1,198 @@
+//! Proptest generators for tokens and latex
+
+use num::BigUint;
+use proptest::prelude::*;
+
+use crate::*;
+
+fn ident() -> impl Strategy<Value = Ident> {
+	let greek: Vec<Greek> = Greek::all().collect();
+	let base = prop_oneof![
+		Just(Ident::Tau),
+		Just(Ident::Pi),
+		proptest::char::ranges(vec!['a'..='z', 'A'..='Z'].into()).prop_map(Ident::AlphabeticChar),
+		proptest::sample::select(greek).prop_map(Ident::Greek),
+		"[a-z]{2,6}"
+			.prop_filter("parsed as a function", |name| {
+				FunctionName::ALL
+					.iter()
+					.all(|function| function.name() != name)
+			})
+			.prop_map(Ident::Named),
+	];
+	(base, proptest::option::of("[a-z0-9]{1,3}")).prop_map(|(base, subscript)| match subscript {
+		Some(subscript) => Ident::Subscripted {
+			base: Box::new(base),
+			subscript,
+		},
+		None => base,
+	})
+}
+
+fn leaf() -> impl Strategy<Value = LatexToken> {
+	prop_oneof![
+		any::<u64>().prop_map(|num| LatexToken::Num(BigUint::from(num))),
+		(any::<u64>(), -20i64..20).prop_map(|(mantissa, exponent)| {
+			LatexToken::Decimal(Decimal {
+				mantissa: BigUint::from(mantissa),
+				exponent,
+			})
+		}),
+		Just(LatexToken::Neg),
+		Just(LatexToken::Mul),
+		Just(LatexToken::Add),
+		proptest::sample::select(vec![
+			RelationKind::Eq,
+			RelationKind::Ne,
+			RelationKind::Lt,
+			RelationKind::Le,
+			RelationKind::Gt,
+			RelationKind::Ge,
+			RelationKind::Approx,
+		])
+		.prop_map(LatexToken::Relation),
+		ident().prop_map(LatexToken::Ident),
+		"[a-z /]{0,5}".prop_map(LatexToken::Text),
+	]
+}
+
+fn is_number(token: &LatexToken) -> bool {
+	matches!(token, LatexToken::Num(_) | LatexToken::Decimal(_))
+}
+
+/// Never has two numbers next to each other,
+/// which would be parsed as one number
+fn tokens(
+	token: impl Strategy<Value = LatexToken>,
+) -> impl Strategy<Value = Vec<Spanned<LatexToken>>> {
+	proptest::collection::vec(token, 1..6).prop_map(|tokens| {
+		let mut ret: Vec<Spanned<LatexToken>> = Vec::new();
+		for token in tokens {
+			if is_number(&token) && ret.last().is_some_and(|last| is_number(last)) {
+				ret.push(LatexToken::Mul.into());
+			}
+			ret.push(token.into());
+		}
+		ret
+	})
+}
+
+pub(crate) fn latex_tokens() -> impl Strategy<Value = Vec<Spanned<LatexToken>>> {
+	let token = leaf().prop_recursive(3, 32, 5, |token| {
+		let inner = || tokens(token.clone());
+		prop_oneof![
+			Just(LatexToken::Exp(vec![])),
+			inner().prop_map(LatexToken::Exp),
+			(
+				proptest::sample::select(vec![Bracket::Round, Bracket::Square, Bracket::Curly]),
+				inner()
+			)
+				.prop_map(|(bracket, inner)| LatexToken::Bracketed(Bracketed { bracket, inner })),
+			inner().prop_map(LatexToken::Abs),
+			(inner(), inner()).prop_map(|(numerator, denominator)| {
+				LatexToken::Frac(Frac {
+					numerator,
+					denominator,
+				})
+			}),
+			(proptest::option::of(inner()), inner())
+				.prop_map(|(index, radicand)| LatexToken::Root(Root { index, radicand })),
+			(
+				proptest::sample::select(FunctionName::ALL.to_vec()),
+				proptest::option::of(inner()),
+				proptest::option::of(inner()),
+				inner()
+			)
+				.prop_map(|(name, subscript, power, argument)| {
+					LatexToken::Function(Function {
+						name,
+						subscript,
+						power,
+						argument,
+					})
+				}),
+			(
+				proptest::sample::select(vec![BigOpKind::Sum, BigOpKind::Prod]),
+				ident(),
+				proptest::option::of(inner()),
+				proptest::option::of(inner()),
+				inner()
+			)
+				.prop_map(|(kind, variable, lower, upper, body)| {
+					LatexToken::BigOp(BigOp {
+						kind,
+						variable: Some(variable),
+						lower,
+						upper,
+						body,
+					})
+				}),
+			(
+				proptest::option::of(inner()),
+				proptest::option::of(inner()),
+				// `d x` would end the integral early,
+				// and `\int ^{2}` has an upper bound
+				inner().prop_filter("ambiguous integral", |body| {
+					!body.contains(&LatexToken::Ident(Ident::AlphabeticChar('d')).into())
+						&& !matches!(body[0].value, LatexToken::Exp(_))
+				}),
+				ident()
+			)
+				.prop_map(|(lower, upper, body, variable)| {
+					LatexToken::Integral(Integral {
+						lower,
+						upper,
+						body,
+						variable,
+					})
+				}),
+			(
+				proptest::sample::select(MatrixKind::ALL.to_vec()),
+				1..4usize,
+				proptest::collection::vec(inner(), 1..10)
+			)
+				.prop_filter_map("no rows", |(kind, columns, cells)| {
+					let rows: Vec<_> = cells.chunks_exact(columns).map(<[_]>::to_vec).collect();
+					(!rows.is_empty()).then_some(LatexToken::Matrix(Matrix { kind, rows }))
+				}),
+			(ident(), inner(), inner()).prop_map(|(variable, approaching, body)| {
+				LatexToken::Limit(Limit {
+					variable,
+					approaching,
+					body,
+				})
+			}),
+		]
+	});
+	tokens(token)
+}
+
+/// Pieces of latex (and plain text math) glued together, valid or not,
+/// which get much further into the parsers than arbitrary strings
+pub(crate) fn latex_like() -> impl Strategy<Value = String> {
+	let piece = prop_oneof![
+		8 => proptest::sample::select(vec![
+			r"\frac", r"\sqrt", r"\left(", r"\right)", r"\left[", r"\right]", r"\left\{",
+			r"\right\}", r"\left|", r"\right|", "{", "}", "(", ")", "[", "]", "|", "^", "_", "&",
+			r"\\", ".", "-", "+", "*", "/", "=", "<", "<=", r"\le", r"\cdot", r"\times10^",
+			r"\begin{pmatrix}", r"\end{pmatrix}", r"\begin{vmatrix}", r"\end{bmatrix}", r"\sum",
+			r"\prod", r"\int", r"\,d", r"\mathrm{d}", r"\lim", r"\to", r"\sin", r"\log",
+			r"\operatorname{", r"\text{", "\"", r"\pi", r"\alpha", r"\newcommand{\f}[1]{#1^{2}}",
+			r"\f", "#1", "sqrt", "root", "abs", "sin", "alpha", " ",
+		])
+		.prop_map(str::to_owned),
+		4 => "[0-9a-z]{1,3}",
+		2 => any::<char>().prop_map(String::from),
+		// latex that parses, cut off anywhere
+		1 => (latex_tokens(), any::<proptest::sample::Index>()).prop_map(|(tokens, index)| {
+			let latex = tokens.into_iter().collect::<LatexTokens>().to_latex();
+			let boundaries: Vec<usize> = latex
+				.char_indices()
+				.map(|(i, _char)| i)
+				.chain([latex.len()])
+				.collect();
+			latex[..*index.get(&boundaries)].to_owned()
+		}),
+	];
+	proptest::collection::vec(piece, 0..12).prop_map(|pieces| pieces.concat())
+}
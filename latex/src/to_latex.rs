@@ -0,0 +1,308 @@
+use crate::{
+	BigOp, Bracket, Bracketed, Decimal, Frac, Function, Ident, Integral, LatexToken, LatexTokens,
+	Limit, Matrix, RelationKind, Root, Spanned,
+};
+
+impl LatexTokens {
+	/// Canonical latex that parses back into the same tokens,
+	/// see [`LatexToken::to_latex`]
+	pub fn to_latex(&self) -> String {
+		let mut latex = String::new();
+		write_tokens(&mut latex, self.tokens());
+		latex
+	}
+}
+
+impl LatexToken {
+	/// Canonical latex, e.g. `\cdot` for every multiplication
+	/// and `\left(`/`\right)` for every bracket.
+	///
+	/// Adjacent [`LatexToken::Num`]s won't parse back the same,
+	/// as spaces between digits are ignored
+	pub fn to_latex(&self) -> String {
+		let mut latex = String::new();
+		self.write_latex(&mut latex);
+		latex
+	}
+
+	fn write_latex(&self, latex: &mut String) {
+		match self {
+			LatexToken::Num(num) => latex.push_str(&num.to_string()),
+			LatexToken::Decimal(decimal) => write_decimal(latex, decimal),
+			LatexToken::Neg => latex.push('-'),
+			LatexToken::Mul => latex.push_str(r"\cdot"),
+			LatexToken::Add => latex.push('+'),
+			LatexToken::Relation(relation) => latex.push_str(relation.to_latex()),
+			LatexToken::Exp(exp) => {
+				// `^{}` doesn't parse
+				latex.push('^');
+				if !exp.is_empty() {
+					write_group(latex, exp);
+				}
+			}
+			LatexToken::Ident(ident) => write_ident(latex, ident),
+			LatexToken::Bracketed(Bracketed { bracket, inner }) => {
+				let (left, right) = match bracket {
+					Bracket::Round => (r"\left(", r"\right)"),
+					Bracket::Square => (r"\left[", r"\right]"),
+					Bracket::Curly => (r"\left\{", r"\right\}"),
+				};
+				latex.push_str(left);
+				write_tokens(latex, inner);
+				latex.push_str(right);
+			}
+			LatexToken::Abs(inner) => {
+				latex.push_str(r"\left|");
+				write_tokens(latex, inner);
+				latex.push_str(r"\right|");
+			}
+			LatexToken::Frac(Frac {
+				numerator,
+				denominator,
+			}) => {
+				latex.push_str(r"\frac");
+				write_group(latex, numerator);
+				write_group(latex, denominator);
+			}
+			LatexToken::Root(Root { index, radicand }) => {
+				latex.push_str(r"\sqrt");
+				if let Some(index) = index {
+					latex.push('[');
+					write_tokens(latex, index);
+					latex.push(']');
+				}
+				write_group(latex, radicand);
+			}
+			LatexToken::Function(Function {
+				name,
+				subscript,
+				power,
+				argument,
+			}) => {
+				latex.push('\\');
+				latex.push_str(name.name());
+				write_scripts(latex, subscript.as_deref(), power.as_deref());
+				latex.push_str(r"\left(");
+				write_tokens(latex, argument);
+				latex.push_str(r"\right)");
+			}
+			LatexToken::BigOp(BigOp {
+				kind,
+				variable,
+				lower,
+				upper,
+				body,
+			}) => {
+				latex.push('\\');
+				latex.push_str(kind.name());
+				match (variable, lower) {
+					(Some(variable), lower) => {
+						latex.push_str("_{");
+						write_ident(latex, variable);
+						if let Some(lower) = lower {
+							latex.push_str(" = ");
+							write_tokens(latex, lower);
+						}
+						latex.push('}');
+					}
+					(None, Some(lower)) => {
+						latex.push('_');
+						write_group(latex, lower);
+					}
+					(None, None) => {}
+				}
+				write_scripts(latex, None, upper.as_deref());
+				latex.push_str(r"\left(");
+				write_tokens(latex, body);
+				latex.push_str(r"\right)");
+			}
+			LatexToken::Integral(Integral {
+				lower,
+				upper,
+				body,
+				variable,
+			}) => {
+				latex.push_str(r"\int");
+				write_scripts(latex, lower.as_deref(), upper.as_deref());
+				latex.push(' ');
+				write_tokens(latex, body);
+				latex.push_str(r" \,d");
+				write_ident(latex, variable);
+			}
+			LatexToken::Limit(Limit {
+				variable,
+				approaching,
+				body,
+			}) => {
+				latex.push_str(r"\lim_{");
+				write_ident(latex, variable);
+				latex.push_str(r" \to ");
+				write_tokens(latex, approaching);
+				latex.push_str(r"}\left(");
+				write_tokens(latex, body);
+				latex.push_str(r"\right)");
+			}
+			LatexToken::Matrix(Matrix { kind, rows }) => {
+				latex.push_str(kind.begin());
+				for (i, row) in rows.iter().enumerate() {
+					if i > 0 {
+						latex.push_str(r" \\ ");
+					}
+					for (j, cell) in row.iter().enumerate() {
+						if j > 0 {
+							latex.push_str(" & ");
+						}
+						write_tokens(latex, cell);
+					}
+				}
+				latex.push_str(kind.end());
+			}
+			LatexToken::Text(text) => {
+				latex.push_str(r"\text{");
+				latex.push_str(text);
+				latex.push('}');
+			}
+			// there's no latex to put back
+			LatexToken::Error(_) => {}
+		}
+	}
+}
+
+impl RelationKind {
+	pub fn to_latex(self) -> &'static str {
+		match self {
+			RelationKind::Eq => "=",
+			RelationKind::Ne => r"\ne",
+			RelationKind::Lt => "<",
+			RelationKind::Le => r"\le",
+			RelationKind::Gt => ">",
+			RelationKind::Ge => r"\ge",
+			RelationKind::Approx => r"\approx",
+		}
+	}
+}
+
+/// Separated by spaces, so commands like `\pi` don't run into the next token
+fn write_tokens(latex: &mut String, tokens: &[Spanned<LatexToken>]) {
+	for (i, token) in tokens.iter().enumerate() {
+		if i > 0 {
+			latex.push(' ');
+		}
+		token.write_latex(latex);
+	}
+}
+
+fn write_group(latex: &mut String, tokens: &[Spanned<LatexToken>]) {
+	latex.push('{');
+	write_tokens(latex, tokens);
+	latex.push('}');
+}
+
+fn write_scripts(
+	latex: &mut String,
+	lower: Option<&[Spanned<LatexToken>]>,
+	upper: Option<&[Spanned<LatexToken>]>,
+) {
+	if let Some(lower) = lower {
+		latex.push('_');
+		write_group(latex, lower);
+	}
+	if let Some(upper) = upper {
+		latex.push('^');
+		write_group(latex, upper);
+	}
+}
+
+/// Decimals needing more zeros than this after the point are written in scientific notation,
+/// so `5\times10^{-99999}` isn't written out in full
+const MAX_LEADING_ZEROS: usize = 8;
+
+/// `0.050` if the exponent is a little negative,
+/// otherwise scientific notation like `5\times10^{3}`
+fn write_decimal(latex: &mut String, decimal: &Decimal) {
+	match plain_decimal(decimal) {
+		Some(plain) => latex.push_str(&plain),
+		None => {
+			latex.push_str(&decimal.mantissa.to_string());
+			latex.push_str(&format!(r"\times10^{{{}}}", decimal.exponent));
+		}
+	}
+}
+
+/// Written out like `0.050`, if the exponent is a little negative
+pub(crate) fn plain_decimal(decimal: &Decimal) -> Option<String> {
+	if decimal.exponent >= 0 {
+		return None;
+	}
+	let digits = decimal.mantissa.to_string();
+	let fraction_len = usize::try_from(decimal.exponent.unsigned_abs()).ok()?;
+	let leading_zeros = (fraction_len + 1).saturating_sub(digits.len());
+	if leading_zeros > MAX_LEADING_ZEROS {
+		return None;
+	}
+	// leading zeros so there is always a whole part
+	let digits = format!("{}{}", "0".repeat(leading_zeros), digits);
+	let (whole, fraction) = digits.split_at(digits.len() - fraction_len);
+	Some(format!("{}.{}", whole, fraction))
+}
+
+fn write_ident(latex: &mut String, ident: &Ident) {
+	match ident {
+		Ident::Tau => latex.push_str(r"\tau"),
+		Ident::Pi => latex.push_str(r"\pi"),
+		Ident::AlphabeticChar(char) => latex.push(*char),
+		Ident::Greek(greek) => {
+			latex.push('\\');
+			latex.push_str(&greek.name());
+		}
+		Ident::Named(name) => {
+			latex.push_str(r"\operatorname{");
+			latex.push_str(name);
+			latex.push('}');
+		}
+		Ident::Subscripted { base, subscript } => {
+			write_ident(latex, base);
+			latex.push_str("_{");
+			latex.push_str(subscript);
+			latex.push('}');
+		}
+	}
+}
+
+#[test]
+fn to_latex() {
+	let latex =
+		r"2x_{1} + \sin\left(\frac{\pi}{2}\right) \cdot 0.050 = \sqrt[3]{y^{2}} \le 1\times10^{3}";
+	let tokens = LatexTokens::parse_from_latex(latex).unwrap();
+	assert_eq!(
+		tokens.to_latex(),
+		r"2 x_{1} + \sin\left(\frac{\pi}{2}\right) \cdot 0.050 = \sqrt[3]{y ^{2}} \le 1\times10^{3}"
+	);
+
+	let tokens = LatexTokens::parse_from_latex(r"\sin x + |y| \ne \left[ 5.5 \right]").unwrap();
+	assert_eq!(
+		tokens.to_latex(),
+		r"\sin\left(x\right) + \left|y\right| \ne \left[5.5\right]"
+	);
+
+	let tokens = LatexTokens::parse_from_latex(r"\sin^2 x + \log_{2}^{3}\left(x\right)").unwrap();
+	assert_eq!(
+		tokens.to_latex(),
+		r"\sin^{2}\left(x\right) + \log_{2}^{3}\left(x\right)"
+	);
+
+	let tokens = LatexTokens::parse_from_latex(r"1\times10^{-9999} + 0.00000000012").unwrap();
+	assert_eq!(tokens.to_latex(), r"1\times10^{-9999} + 12\times10^{-11}");
+}
+
+#[cfg(test)]
+proptest::proptest! {
+	#[test]
+	fn to_latex_round_trips(tokens in crate::strategies::latex_tokens()) {
+		let tokens: LatexTokens = tokens.into_iter().collect();
+		let latex = tokens.to_latex();
+		let parsed = LatexTokens::parse_from_latex(&latex)
+			.unwrap_or_else(|err| panic!("{} failed to parse: {}", latex, err));
+		proptest::prop_assert_eq!(parsed.tokens(), tokens.tokens(), "{}", latex);
+	}
+}
@@ -0,0 +1,176 @@
+use num::BigUint;
+
+use crate::{
+	BigOp, Bracketed, Decimal, Frac, Function, Ident, Integral, LatexToken, LatexTokens, Limit,
+	Matrix, RelationKind, Root, Span, Spanned,
+};
+
+/// Edits tokens in place, see [`crate::TokenFold`] to rebuild them instead.
+///
+/// Every method defaults to visiting any tokens inside,
+/// including the exponent and bodies with bound variables,
+/// unlike [`crate::TokenVisitor`]
+pub trait TokenVisitorMut {
+	/// Default visits each token,
+	/// override to add or remove tokens
+	fn visit_tokens_mut(&mut self, tokens: &mut Vec<Spanned<LatexToken>>) {
+		for token in tokens {
+			self.visit_token_mut(token);
+		}
+	}
+	/// Default visits by kind of token,
+	/// override to replace a token with a different kind
+	fn visit_token_mut(&mut self, token: &mut Spanned<LatexToken>) {
+		token.value.visit_mut(self);
+	}
+	fn visit_num_mut(&mut self, num: &mut BigUint) {
+		_ = num;
+	}
+	fn visit_decimal_mut(&mut self, decimal: &mut Decimal) {
+		_ = decimal;
+	}
+	fn visit_neg_mut(&mut self) {}
+	fn visit_mul_mut(&mut self) {}
+	fn visit_add_mut(&mut self) {}
+	fn visit_relation_mut(&mut self, relation: &mut RelationKind) {
+		_ = relation;
+	}
+	fn visit_exp_mut(&mut self, exp: &mut Vec<Spanned<LatexToken>>) {
+		self.visit_tokens_mut(exp);
+	}
+	/// Bound variables like the `i` in `\sum_{i=1}^{n}` are visited too
+	fn visit_ident_mut(&mut self, ident: &mut Ident) {
+		_ = ident;
+	}
+	fn visit_bracketed_mut(&mut self, bracketed: &mut Bracketed) {
+		self.visit_tokens_mut(&mut bracketed.inner);
+	}
+	fn visit_abs_mut(&mut self, abs: &mut Vec<Spanned<LatexToken>>) {
+		self.visit_tokens_mut(abs);
+	}
+	fn visit_frac_mut(&mut self, frac: &mut Frac) {
+		self.visit_tokens_mut(&mut frac.numerator);
+		self.visit_tokens_mut(&mut frac.denominator);
+	}
+	fn visit_root_mut(&mut self, root: &mut Root) {
+		if let Some(index) = &mut root.index {
+			self.visit_tokens_mut(index);
+		}
+		self.visit_tokens_mut(&mut root.radicand);
+	}
+	fn visit_function_mut(&mut self, function: &mut Function) {
+		if let Some(subscript) = &mut function.subscript {
+			self.visit_tokens_mut(subscript);
+		}
+		if let Some(power) = &mut function.power {
+			self.visit_tokens_mut(power);
+		}
+		self.visit_tokens_mut(&mut function.argument);
+	}
+	fn visit_big_op_mut(&mut self, big_op: &mut BigOp) {
+		if let Some(variable) = &mut big_op.variable {
+			self.visit_ident_mut(variable);
+		}
+		for bound in big_op.lower.iter_mut().chain(&mut big_op.upper) {
+			self.visit_tokens_mut(bound);
+		}
+		self.visit_tokens_mut(&mut big_op.body);
+	}
+	fn visit_integral_mut(&mut self, integral: &mut Integral) {
+		for bound in integral.lower.iter_mut().chain(&mut integral.upper) {
+			self.visit_tokens_mut(bound);
+		}
+		self.visit_tokens_mut(&mut integral.body);
+		self.visit_ident_mut(&mut integral.variable);
+	}
+	fn visit_limit_mut(&mut self, limit: &mut Limit) {
+		self.visit_ident_mut(&mut limit.variable);
+		self.visit_tokens_mut(&mut limit.approaching);
+		self.visit_tokens_mut(&mut limit.body);
+	}
+	fn visit_matrix_mut(&mut self, matrix: &mut Matrix) {
+		for cell in matrix.rows.iter_mut().flatten() {
+			self.visit_tokens_mut(cell);
+		}
+	}
+	fn visit_text_mut(&mut self, text: &mut String) {
+		_ = text;
+	}
+	fn visit_error_mut(&mut self, span: Span) {
+		_ = span;
+	}
+}
+
+impl LatexToken {
+	pub fn visit_mut<T>(&mut self, visitor: &mut T)
+	where
+		T: TokenVisitorMut + ?Sized,
+	{
+		match self {
+			LatexToken::Num(num) => visitor.visit_num_mut(num),
+			LatexToken::Decimal(decimal) => visitor.visit_decimal_mut(decimal),
+			LatexToken::Neg => visitor.visit_neg_mut(),
+			LatexToken::Mul => visitor.visit_mul_mut(),
+			LatexToken::Add => visitor.visit_add_mut(),
+			LatexToken::Relation(relation) => visitor.visit_relation_mut(relation),
+			LatexToken::Exp(exp) => visitor.visit_exp_mut(exp),
+			LatexToken::Ident(ident) => visitor.visit_ident_mut(ident),
+			LatexToken::Bracketed(bracketed) => visitor.visit_bracketed_mut(bracketed),
+			LatexToken::Abs(abs) => visitor.visit_abs_mut(abs),
+			LatexToken::Frac(frac) => visitor.visit_frac_mut(frac),
+			LatexToken::Root(root) => visitor.visit_root_mut(root),
+			LatexToken::Function(function) => visitor.visit_function_mut(function),
+			LatexToken::BigOp(big_op) => visitor.visit_big_op_mut(big_op),
+			LatexToken::Integral(integral) => visitor.visit_integral_mut(integral),
+			LatexToken::Limit(limit) => visitor.visit_limit_mut(limit),
+			LatexToken::Matrix(matrix) => visitor.visit_matrix_mut(matrix),
+			LatexToken::Text(text) => visitor.visit_text_mut(text),
+			LatexToken::Error(span) => visitor.visit_error_mut(*span),
+		}
+	}
+}
+
+impl LatexTokens {
+	pub fn visit_mut<T>(&mut self, visitor: &mut T)
+	where
+		T: TokenVisitorMut + ?Sized,
+	{
+		visitor.visit_tokens_mut(&mut self.0);
+	}
+}
+
+#[test]
+fn visit_mut() {
+	/// Renames `x` to `y`, even as a bound variable
+	struct Rename;
+	impl TokenVisitorMut for Rename {
+		fn visit_ident_mut(&mut self, ident: &mut Ident) {
+			if *ident == Ident::AlphabeticChar('x') {
+				*ident = Ident::AlphabeticChar('y');
+			}
+		}
+	}
+	let mut tokens =
+		LatexTokens::parse_from_latex(r"x^{x} + \sum_{x=1}^{3} \frac{x}{2} + \int x \,dx").unwrap();
+	let expected =
+		LatexTokens::parse_from_latex(r"y^{y} + \sum_{y=1}^{3} \frac{y}{2} + \int y \,dy").unwrap();
+	tokens.visit_mut(&mut Rename);
+	assert_eq!(tokens.tokens(), expected.tokens());
+
+	/// Drops `\text{}` labels, and works as `dyn`
+	struct RemoveText;
+	impl TokenVisitorMut for RemoveText {
+		fn visit_tokens_mut(&mut self, tokens: &mut Vec<Spanned<LatexToken>>) {
+			tokens.retain(|token| !matches!(token.value, LatexToken::Text(_)));
+			for token in tokens {
+				self.visit_token_mut(token);
+			}
+		}
+	}
+	let mut tokens =
+		LatexTokens::parse_from_latex(r"5\text{m} = \left(2\text{m} + 3\right)").unwrap();
+	let expected = LatexTokens::parse_from_latex(r"5 = \left(2 + 3\right)").unwrap();
+	let visitor: &mut dyn TokenVisitorMut = &mut RemoveText;
+	tokens.visit_mut(visitor);
+	assert_eq!(tokens.tokens(), expected.tokens());
+}